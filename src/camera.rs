@@ -50,7 +50,7 @@ impl Camera {
 #[cfg(test)]
 mod tests {
 use crate::{
-    Camera,
+    camera::Camera,
     vec3::Vec3,
 };
 use assert_approx_eq::assert_approx_eq;
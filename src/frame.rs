@@ -0,0 +1,81 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal shading frame, with `n` as the local z axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    pub fn new(s: Vec3, t: Vec3, n: Vec3) -> Frame {
+        Frame {
+            s,
+            t,
+            n,
+        }
+    }
+    pub fn from_normal(n: &Vec3) -> Frame {
+        let sign = 1.0_f64.copysign(n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        Frame {
+            s: Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            t: Vec3::new(b, sign + n.y * n.y * a, -n.y),
+            n: *n,
+        }
+    }
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        self.s * v.x + self.t * v.y + self.n * v.z
+    }
+    /// This frame with `n` negated when `wo` lies below it, so that `wo` is
+    /// always in the upper hemisphere of the result.
+    pub fn facing(&self, wo: &Vec3) -> Frame {
+        if wo.dot(&self.n) < 0.0 {
+            Frame {
+                n: -self.n,
+                ..*self
+            }
+        } else {
+            *self
+        }
+    }
+    /// Local directions in the frame facing `wo`.
+    pub fn to_local_facing(&self, wo: &Vec3, wi: &Vec3) -> (Vec3, Vec3) {
+        let frame = self.facing(wo);
+        (frame.to_local(wo), frame.to_local(wi))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = Frame::from_normal(&Vec3::new(0.3, -0.5, 0.8).normalize());
+        assert_approx_eq!(frame.s.dot(&frame.t), 0.0);
+        assert_approx_eq!(frame.s.dot(&frame.n), 0.0);
+        assert_approx_eq!(frame.t.dot(&frame.n), 0.0);
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        let round_trip = frame.to_world(&frame.to_local(&v));
+        assert_approx_eq!(round_trip.x, v.x);
+        assert_approx_eq!(round_trip.y, v.y);
+        assert_approx_eq!(round_trip.z, v.z);
+    }
+
+    #[test]
+    fn test_facing_round_trip_below_surface() {
+        let frame = Frame::from_normal(&Vec3::new(0.0, 0.0, 1.0)).facing(&Vec3::new(0.3, 0.0, -0.9));
+        let local = frame.to_local(&Vec3::new(0.3, 0.0, -0.9));
+        assert!(local.z > 0.0);
+        let round_trip = frame.to_world(&local);
+        assert_approx_eq!(round_trip.x, 0.3);
+        assert_approx_eq!(round_trip.z, -0.9);
+    }
+}
@@ -5,11 +5,16 @@ use crate::{
     ray::Ray,
     geometry::plane::Plane,
     vec3::Vec3,
+    frame::Frame,
 };
 
 pub trait Geometry{
     fn hit(&self, ray: &Ray) -> Option<Hit>;
-    fn sample(&self, p: &Vec3) -> Vec3;
+    /// Point drawn uniformly over the surface, with its density per unit area,
+    /// or `None` for unbounded surfaces.
+    fn sample(&self) -> Option<SurfaceSample>;
+    /// Area density with which `sample` returns `hit`.
+    fn pdf(&self, hit: &Hit) -> f64;
     fn normal_at(&self, p: &Vec3) -> Vec3;
     fn hit_point(&self, ray: &Ray) -> Vec3;
 }
@@ -23,4 +28,55 @@ pub struct Hit {
     pub distance: f64,
     pub normal: Vec3,
     pub point: Vec3,
+    /// Hit position in the geometry's own coordinate system, for solid textures.
+    pub object_point: Vec3,
+    pub uv: (f64, f64),
+    /// Surface direction of increasing u. May be zero where the parameterization degenerates.
+    pub tangent: Vec3,
+    /// Barycentric coordinates of the second and third vertex of the triangle hit.
+    pub barycentrics: (f64, f64),
+}
+
+/// Barycentric coordinates of `uv` within its unit cell of texture space,
+/// split into two triangles along the diagonal, for surfaces that are not
+/// made of triangles but are tessellated by their parameterization.
+pub fn cell_barycentrics(uv: (f64, f64)) -> (f64, f64) {
+    let (u, v) = (uv.0 - uv.0.floor(), uv.1 - uv.1.floor());
+    if u + v <= 1.0 {
+        (u, v)
+    } else {
+        (1.0 - v, 1.0 - u)
+    }
+}
+
+/// Point on a surface, described as a hit with zero distance.
+#[derive(Debug, Clone)]
+pub struct SurfaceSample {
+    pub hit: Hit,
+    pub pdf: f64,
+}
+
+impl Hit {
+    /// Hit at the origin facing +z, with its tangent along x, for tests.
+    #[cfg(test)]
+    pub fn at_origin() -> Hit {
+        Hit {
+            distance: 1.0,
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            point: Vec3::new(0.0, 0.0, 0.0),
+            object_point: Vec3::new(0.0, 0.0, 0.0),
+            uv: (0.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            barycentrics: (0.0, 0.0),
+        }
+    }
+    /// Shading frame with `s` along the tangent and `t` along increasing v.
+    pub fn frame(&self) -> Frame {
+        let tangent = self.tangent - self.normal * self.normal.dot(&self.tangent);
+        if tangent.dot(&tangent) < 1e-12 {
+            return Frame::from_normal(&self.normal);
+        }
+        let s = tangent.normalize();
+        Frame::new(s, self.normal.cross(&s), self.normal)
+    }
 }
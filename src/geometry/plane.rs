@@ -2,7 +2,8 @@ use crate::{
     vec3::Vec3,
     geometry::Geometry,
     ray::Ray,
-    geometry::Hit,
+    geometry::{self, Hit, SurfaceSample},
+    frame::Frame,
 };
 
 #[derive(Debug, Clone)]
@@ -18,7 +19,15 @@ impl Plane {
             distance,
         }
     }
-    pub fn default() -> Plane {
+    /// Coordinates of `p` along the plane's tangent axes.
+    pub fn uv(&self, p: &Vec3) -> (f64, f64) {
+        let frame = Frame::from_normal(&self.normal);
+        (p.dot(&frame.s), p.dot(&frame.t))
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Plane {
         Plane {
             normal: Vec3::new(0.0, -1.0, 0.0),
             distance: 0.0,
@@ -28,20 +37,30 @@ impl Plane {
 
 impl Geometry for Plane {
     fn hit(&self, ray: &Ray) -> Option<Hit> {
-        let denom = self.normal.dot(&ray.direction);
+        let direction = ray.direction.normalize();
+        let denom = self.normal.dot(&direction);
         let dist = (self.distance - self.normal.dot(&ray.origin)) / denom;
-        if dist > 0.0 {
+        if dist > 0.0 && dist.is_finite() {
+            let point = ray.origin + direction * dist;
+            let uv = self.uv(&point);
             Some(Hit {
                 distance: dist,
                 normal: self.normal,
-                point: ray.origin + ray.direction * dist,
+                point,
+                object_point: point,
+                uv,
+                tangent: Frame::from_normal(&self.normal).s,
+                barycentrics: geometry::cell_barycentrics(uv),
             })
         } else {
             None
         }
     }
-    fn sample(&self, Vec3 { x, y, z }: &Vec3) -> Vec3 {
-        Vec3::new(*x, *y, *z)
+    fn sample(&self) -> Option<SurfaceSample> {
+        None
+    }
+    fn pdf(&self, _hit: &Hit) -> f64 {
+        0.0
     }
     fn normal_at(&self, _: &Vec3) -> Vec3 {
         self.normal
@@ -1,7 +1,9 @@
+use std::f64::consts::PI;
 use crate::{
     vec3::Vec3,
     ray::Ray,
-    geometry::Hit,
+    geometry::{self, Hit, SurfaceSample},
+    sampling,
 };
 
 use super::Geometry;
@@ -19,41 +21,63 @@ impl Sphere {
             radius,
         }
     }
+    fn hit_at(&self, point: Vec3, distance: f64) -> Hit {
+        let normal = self.normal_at(&point);
+        let uv = self.uv(&normal);
+        Hit {
+            distance,
+            normal,
+            point,
+            object_point: point - self.center,
+            uv,
+            tangent: self.tangent(&normal),
+            barycentrics: geometry::cell_barycentrics(uv),
+        }
+    }
+    /// Spherical coordinates of a unit normal, with v running from the -y to the +y pole.
+    pub fn uv(&self, normal: &Vec3) -> (f64, f64) {
+        let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+        let phi = (-normal.z).atan2(normal.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+    pub fn tangent(&self, normal: &Vec3) -> Vec3 {
+        let tangent = Vec3::new(normal.z, 0.0, -normal.x);
+        if tangent.dot(&tangent) > 0.0 {
+            tangent.normalize()
+        } else {
+            tangent
+        }
+    }
 }
 
 impl Geometry for Sphere {
     fn hit(&self, ray: &Ray) -> Option<Hit> {
+        let direction = ray.direction.normalize();
         let oc = ray.origin - self.center;
-        let a = ray.direction.dot(&ray.direction);
-        let b = oc.dot(&ray.direction);
+        let b = oc.dot(&direction);
         let c = oc.dot(&oc) - self.radius * self.radius;
-        let discriminant = b * b - a * c;
-        if discriminant > 0.0 {
-            let temp = (-b - discriminant.sqrt()) / a;
-            if temp > 0.0 {
-                Some(Hit {
-                    distance: temp,
-                    normal: (ray.origin + ray.direction * temp) - self.center,
-                    point: ray.origin + ray.direction * temp,
-                })
-            } else {
-                let temp = (-b + discriminant.sqrt()) / a;
-                if temp > 0.0 {
-                    Some(Hit {
-                        distance: temp,
-                        normal: (ray.origin + ray.direction * temp) - self.center,
-                        point: ray.origin + ray.direction * temp,
-                    })
-                } else {
-                    None
-                }
-            }
-        } else {
-            None
+        let discriminant = b * b - c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let mut distance = -b - discriminant.sqrt();
+        if distance <= 0.0 {
+            distance = -b + discriminant.sqrt();
+        }
+        if distance <= 0.0 {
+            return None;
         }
+        Some(self.hit_at(ray.origin + direction * distance, distance))
     }
-    fn sample(&self, _: &Vec3) -> Vec3 {
-        Vec3::new(0.0, 0.0, 0.0)
+    fn sample(&self) -> Option<SurfaceSample> {
+        let normal = sampling::uniform_sphere(rand::random(), rand::random());
+        Some(SurfaceSample {
+            hit: self.hit_at(self.center + normal * self.radius, 0.0),
+            pdf: 1.0 / (4.0 * PI * self.radius * self.radius),
+        })
+    }
+    fn pdf(&self, _hit: &Hit) -> f64 {
+        1.0 / (4.0 * PI * self.radius * self.radius)
     }
     fn normal_at(&self, p: &Vec3) -> Vec3 {
         (p - &self.center).normalize()
@@ -78,6 +102,10 @@ mod tests{
             distance: 4.0,
             normal: Vec3::new(0.0, 0.0, -1.0),
             point: Vec3::new(0.0, 0.0, -1.0),
+            object_point: Vec3::new(0.0, 0.0, -1.0),
+            uv: (0.75, 0.5),
+            tangent: Vec3::new(-1.0, 0.0, 0.0),
+            barycentrics: (0.5, 0.25),
         });
         test_hit(&sphere, &ray, expected);
     }
@@ -88,4 +116,11 @@ mod tests{
         let expected = None;
         test_hit(&sphere, &ray, expected);
     }
+    #[test]
+    fn test_sphere_sample() {
+        let sphere = Sphere::new(Vec3::new(1.0, 2.0, 3.0), 2.0);
+        let sample = sphere.sample().unwrap();
+        assert!(((sample.hit.point - sphere.center).length() - 2.0).abs() < 1e-9);
+        assert!((sample.pdf - 1.0 / (16.0 * PI)).abs() < 1e-12);
+    }
 }
\ No newline at end of file
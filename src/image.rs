@@ -1,6 +1,7 @@
 use image::{RgbImage, DynamicImage};
 use std::ops::{
     Add,
+    Sub,
     Mul,
     Div,
     AddAssign,
@@ -13,7 +14,7 @@ pub struct Image {
     hight: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub red: f64,
     pub green: f64,
@@ -56,6 +57,15 @@ impl Color {
             blue,
         }
     }
+    pub fn is_black(&self) -> bool {
+        self.red <= 0.0 && self.green <= 0.0 && self.blue <= 0.0
+    }
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        *self * (1.0 - t) + *other * t
+    }
 }
 
 impl Div<f64> for Color {
@@ -80,6 +90,17 @@ impl Add for Color {
     }
 }
 
+impl Sub for Color {
+    type Output = Color;
+    fn sub(self, rhs: Color) -> Color {
+        Color {
+            red: self.red - rhs.red,
+            green: self.green - rhs.green,
+            blue: self.blue - rhs.blue,
+        }
+    }
+}
+
 impl Mul for Color {
     type Output = Color;
     fn mul(self, rhs: Color) -> Color {
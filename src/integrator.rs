@@ -0,0 +1,65 @@
+pub mod path;
+pub mod whitted;
+pub mod ambient_occlusion;
+pub mod debug;
+pub mod bidirectional;
+pub mod photon;
+
+use crate::{
+    scene::Scene,
+    ray::Ray,
+    image::Color,
+    integrator::{
+        path::PathTracer,
+        bidirectional::Bidirectional,
+        photon::PhotonMapper,
+        whitted::Whitted,
+        ambient_occlusion::AmbientOcclusion,
+        debug::{DebugIntegrator, DebugMode},
+    },
+};
+
+/// Light transport algorithm computing what the camera sees along a ray.
+pub trait Integrator {
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color;
+    /// Called before each pass of one sample per pixel, numbered from 0, for
+    /// integrators that precompute something per pass.
+    fn begin_pass(&mut self, _scene: &Scene, _pass: u32) {}
+}
+
+/// Integrator chosen by name, for selecting one at runtime: `path`, `bdpt`,
+/// `photon`, `whitted`, `ao`, `normals`, `depth`, `uv` or `barycentrics`. `ao`
+/// and `depth` take a distance after a colon, as in `ao:2.5`.
+pub fn from_name(name: &str, max_depth: u32) -> Option<Box<dyn Integrator>> {
+    let integrator: Box<dyn Integrator> = match name {
+        "path" => Box::new(PathTracer::new(max_depth)),
+        "bdpt" => Box::new(Bidirectional::new(max_depth)),
+        "photon" => Box::new(PhotonMapper::new(max_depth, 100_000, 0.05)),
+        "whitted" => Box::new(Whitted::new(max_depth)),
+        _ if name.starts_with("ao") => Box::new(AmbientOcclusion::from_name(name)?),
+        _ => Box::new(DebugIntegrator::new(DebugMode::from_name(name)?)),
+    };
+    Some(integrator)
+}
+
+/// One estimate of the radiance along `ray` following BSDF samples alone for
+/// up to `max_depth` bounces, picking up emission and the background where
+/// paths land. Slow to converge but free of light sampling, so integrators
+/// are checked against it.
+#[cfg(test)]
+pub(crate) fn bsdf_sampled(scene: &Scene, ray: Ray, max_depth: u32) -> Color {
+    const OFFSET: f64 = 1e-6;
+    let (mut ray, mut throughput, mut radiance) = (ray, Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+    for _ in 0..=max_depth {
+        let Some((hit, object)) = scene.intersect(&ray) else {
+            return radiance + throughput * scene.background(&ray.direction);
+        };
+        let wo = -ray.direction.normalize();
+        radiance += throughput * object.material.emitted(&wo, &hit);
+        let Some(sample) = object.material.sample(&wo, &hit) else { break };
+        throughput = throughput * sample.value * (sample.direction.dot(&hit.normal).abs() / sample.pdf);
+        let side = if sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+        ray = Ray::new(hit.point + hit.normal * (OFFSET * side), sample.direction);
+    }
+    radiance
+}
@@ -0,0 +1,96 @@
+use crate::{
+    integrator::Integrator,
+    scene::Scene,
+    ray::{Ray, RayKind},
+    image::Color,
+    sampling,
+};
+
+/// Step taken off a surface before tracing occlusion rays from it.
+const OCCLUSION_OFFSET: f64 = 1e-6;
+/// Radius used when chosen by name without one.
+const DEFAULT_RADIUS: f64 = 1.0;
+/// Rays per hit when chosen by name.
+const DEFAULT_SAMPLES: u32 = 16;
+
+/// Fraction of the hemisphere above each hit left open within `radius`,
+/// estimated with `samples` cosine-weighted rays. Misses are white.
+pub struct AmbientOcclusion {
+    pub radius: f64,
+    pub samples: u32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(radius: f64, samples: u32) -> AmbientOcclusion {
+        AmbientOcclusion {
+            radius,
+            samples,
+        }
+    }
+    /// `ao`, or `ao:<radius>` for another radius than the default.
+    pub fn from_name(name: &str) -> Option<AmbientOcclusion> {
+        let radius = match name.strip_prefix("ao") {
+            Some("") => DEFAULT_RADIUS,
+            Some(radius) => radius.strip_prefix(':')?.parse().ok()?,
+            None => return None,
+        };
+        Some(AmbientOcclusion::new(radius, DEFAULT_SAMPLES))
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        let hit = match scene.intersect(&ray) {
+            Some((hit, _)) => hit,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+        let mut frame = hit.frame();
+        if ray.direction.dot(&frame.n) > 0.0 {
+            frame.n = -frame.n;
+            frame.t = -frame.t;
+        }
+        let origin = hit.point + frame.n * OCCLUSION_OFFSET;
+        let mut open = 0;
+        for _ in 0..self.samples {
+            let local = sampling::cosine_hemisphere(rand::random(), rand::random());
+            let occlusion_ray = Ray::new(origin, frame.to_world(&local)).with_kind(RayKind::Shadow);
+            match scene.intersect(&occlusion_ray) {
+                Some((occluder, _)) if occluder.distance < self.radius => {}
+                _ => open += 1,
+            }
+        }
+        let visibility = open as f64 / self.samples.max(1) as f64;
+        Color::new(visibility, visibility, visibility)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        vec3::Vec3,
+        object::Object,
+        material,
+        geometry::plane::Plane,
+    };
+
+    #[test]
+    fn test_occlusion_within_radius() {
+        // A floor under a ceiling one unit above it.
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(material::default(), Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0))));
+        scene.add_object(Object::new(material::default(), Box::new(Plane::new(Vec3::new(0.0, -1.0, 0.0), -1.0))));
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_approx_eq!(AmbientOcclusion::new(0.5, 64).radiance(&scene, ray).red, 1.0);
+        assert_approx_eq!(AmbientOcclusion::new(1e9, 64).radiance(&scene, ray).red, 0.0);
+    }
+
+    #[test]
+    fn test_from_name_reads_radius() {
+        assert_eq!(AmbientOcclusion::from_name("ao").unwrap().radius, DEFAULT_RADIUS);
+        assert_eq!(AmbientOcclusion::from_name("ao:2.5").unwrap().radius, 2.5);
+        assert!(AmbientOcclusion::from_name("ao:far").is_none());
+        assert!(AmbientOcclusion::from_name("aox").is_none());
+    }
+}
@@ -0,0 +1,619 @@
+use crate::{
+    integrator::{Integrator, path::PathTracer},
+    scene::Scene,
+    object::{Object, Occlusion, Visibility},
+    ray::{Ray, RayKind},
+    image::Color,
+    light::{LightSample, sampler::LightIndex},
+    geometry::Hit,
+    vec3::Vec3,
+    sampling,
+};
+
+/// Bounces traced before subpaths may be ended by Russian roulette.
+const ROULETTE_DEPTH: u32 = 3;
+/// Highest probability of continuing a subpath, so bright subpaths still end.
+const MAX_SURVIVAL: f64 = 0.95;
+/// Step taken off a surface before tracing the next bounce from it.
+const BOUNCE_OFFSET: f64 = 1e-6;
+/// Gap left short of the far end of a connection, so the surface there does
+/// not occlude it.
+const CONNECTION_OFFSET: f64 = 1e-6;
+
+/// Bidirectional path tracer. Each camera ray starts a subpath from the
+/// camera and another from a light drawn by power, then joins every vertex
+/// of one to every vertex of the other, weighting the ways of sampling each
+/// path with the power heuristic. Light subpaths find light that squeezes
+/// through small openings or reaches surfaces through glass. Paths ending on
+/// the camera lens are not traced, as they land in other pixels. Lights at
+/// infinity cannot start subpaths and are sampled as in `PathTracer`. Light
+/// linking decides whether a light reaches the first surface after it on
+/// every path. Light scattered beneath a surface is only gathered directly
+/// from the lights, at the camera subpath's vertices, as in `PathTracer`.
+///
+/// Objects hidden from some kinds of ray would make the ways of sampling a
+/// path disagree, since connections are shadow rays and subpaths are not.
+/// Scenes with any are not supported: `begin_pass` reports them once and the
+/// render falls back to path tracing.
+pub struct Bidirectional {
+    pub max_depth: u32,
+    /// Set by `begin_pass` for scenes with objects hidden from some rays.
+    path_traced: bool,
+}
+
+impl Bidirectional {
+    pub fn new(max_depth: u32) -> Bidirectional {
+        Bidirectional {
+            max_depth,
+            path_traced: false,
+        }
+    }
+    /// Subpath from a light drawn in proportion to its power; empty when no
+    /// light in the scene can start one.
+    fn light_path<'a>(&self, scene: &'a Scene, max_vertices: usize) -> Vec<Vertex<'a>> {
+        let mut path = Vec::new();
+        let (index, probability) = match scene.light_sampler().pick() {
+            Some(pick) => pick,
+            None => return path,
+        };
+        let (emission, hit) = match scene.sample_emission(index) {
+            Some(sample) => sample,
+            None => return path,
+        };
+        if emission.pdf_position <= 0.0 || emission.pdf_direction <= 0.0 || emission.radiance.is_black() {
+            return path;
+        }
+        let pdf = probability * emission.pdf_position;
+        let light = Vertex::new(VertexKind::Light(index, hit), emission.point, emission.normal, Color::new(1.0, 1.0, 1.0) / pdf, pdf);
+        let cos = if light.has_surface() { emission.direction.dot(&emission.normal).abs() } else { 1.0 };
+        let beta = emission.radiance * (cos / (pdf * emission.pdf_direction));
+        let side = if emission.direction.dot(&emission.normal) < 0.0 { -1.0 } else { 1.0 };
+        let ray = Ray::new(emission.point + emission.normal * (BOUNCE_OFFSET * side), emission.direction)
+            .with_kind(RayKind::Reflection);
+        path.push(light);
+        self.walk(scene, ray, beta, emission.pdf_direction, &mut path, max_vertices, None);
+        if let Some(Vertex { kind: VertexKind::Surface { object, .. }, point, .. }) = path.get(1) {
+            if !lights(scene, index, object, point) {
+                path.truncate(1);
+            }
+        }
+        path
+    }
+    /// Extends `path` with the surfaces found by following BSDF samples from
+    /// `ray`, which the last vertex chose with solid angle density `pdf`.
+    /// On camera subpaths `color` gathers the lights the rays meet, including
+    /// one bounce past the last vertex kept.
+    #[allow(clippy::too_many_arguments)]
+    fn walk<'a>(
+        &self,
+        scene: &'a Scene,
+        ray: Ray,
+        beta: Color,
+        pdf: f64,
+        path: &mut Vec<Vertex<'a>>,
+        max_vertices: usize,
+        mut color: Option<&mut Color>,
+    ) {
+        let (mut ray, mut beta, mut pdf) = (ray, beta, pdf);
+        let mut bounce = 0;
+        loop {
+            let hit_object = scene.intersect(&ray);
+            if let Some(color) = color.as_deref_mut() {
+                let nearest = hit_object.as_ref().map_or(f64::INFINITY, |(hit, _)| hit.distance);
+                *color += beta * light_hits(scene, &ray, nearest, path, pdf);
+            }
+            let (hit, object) = match hit_object {
+                Some(hit_object) => hit_object,
+                None => break,
+            };
+            let wo = -ray.direction.normalize();
+            let previous = path.last().unwrap();
+            let mut vertex = Vertex::new(VertexKind::Surface { hit: hit.clone(), object, wo }, hit.point, hit.normal, beta, 0.0);
+            vertex.pdf_fwd = previous.to_area(pdf, &vertex);
+            if let Some(color) = color.as_deref_mut() {
+                *color += beta * emission_hit(scene, path, &vertex);
+            }
+            if path.len() == max_vertices {
+                break;
+            }
+            path.push(vertex);
+            if let Some(color) = color.as_deref_mut() {
+                *color += beta * object.subsurface(&wo, &hit, scene, Occlusion::Exact);
+            }
+            if path.len() == max_vertices && color.is_none() {
+                break;
+            }
+            let sample = match object.material.sample(&wo, &hit) {
+                Some(sample) if sample.pdf > 0.0 => sample,
+                _ => break,
+            };
+            // Light subpaths carry light towards the sampled direction, so the
+            // BSDF is evaluated that way round; it differs through refraction.
+            let value = if color.is_none() && !sample.specular {
+                object.material.eval(&sample.direction, &wo, &hit)
+            } else {
+                sample.value
+            };
+            beta = beta * value * (sample.direction.dot(&hit.normal).abs() / sample.pdf);
+            if beta.is_black() {
+                break;
+            }
+            if bounce >= ROULETTE_DEPTH {
+                let survival = beta.red.max(beta.green).max(beta.blue).min(MAX_SURVIVAL);
+                if rand::random::<f64>() >= survival {
+                    break;
+                }
+                beta = beta / survival;
+            }
+            bounce += 1;
+            // Specular bounces have no density to weigh; like the vertices
+            // they lead to, they are skipped by the weights.
+            let n = path.len();
+            let (pdf_fwd, pdf_rev) = if sample.specular {
+                path[n - 1].delta = true;
+                (0.0, 0.0)
+            } else {
+                (sample.pdf, object.material.pdf(&sample.direction, &wo, &hit))
+            };
+            path[n - 2].pdf_rev = path[n - 1].to_area(pdf_rev, &path[n - 2]);
+            pdf = pdf_fwd;
+            let side = if sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+            let origin = hit.point + hit.normal * (BOUNCE_OFFSET * side);
+            ray = Ray::new(origin, sample.direction).with_kind(RayKind::Reflection);
+        }
+    }
+}
+
+impl Integrator for Bidirectional {
+    fn begin_pass(&mut self, scene: &Scene, pass: u32) {
+        if pass > 0 {
+            return;
+        }
+        self.path_traced = scene.objects().iter().any(|object| object.visibility != Visibility::default());
+        if self.path_traced {
+            eprintln!("bdpt: objects hidden from some rays are not supported; path tracing instead");
+        }
+    }
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        if self.path_traced {
+            return PathTracer::new(self.max_depth).radiance(scene, ray);
+        }
+        // The camera, up to max_depth + 1 surfaces and a point on a light.
+        let max_vertices = self.max_depth as usize + 3;
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        let camera = Vertex::new(VertexKind::Camera, ray.origin, Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0);
+        let mut camera_path = vec![camera];
+        self.walk(scene, ray, Color::new(1.0, 1.0, 1.0), 1.0, &mut camera_path, max_vertices - 1, Some(&mut color));
+        let light_path = self.light_path(scene, max_vertices - 2);
+        for t in 2..=camera_path.len() {
+            color += infinite_lights(scene, &camera_path[t - 1]);
+            for s in 1..=light_path.len().min(max_vertices - t) {
+                color += connect(scene, &light_path[..s], &camera_path[..t]);
+            }
+        }
+        color
+    }
+}
+
+enum VertexKind<'a> {
+    Camera,
+    /// Point on a light, with the surface hit for emissive objects.
+    Light(LightIndex, Option<Hit>),
+    /// Scattering surface; `wo` points back along the subpath that found it.
+    Surface { hit: Hit, object: &'a Object, wo: Vec3 },
+}
+
+struct Vertex<'a> {
+    kind: VertexKind<'a>,
+    point: Vec3,
+    /// Geometric normal; zero at the camera and at point lights.
+    normal: Vec3,
+    /// Throughput of the subpath up to this vertex.
+    beta: Color,
+    /// Area density of this vertex as sampled by its own subpath.
+    pdf_fwd: f64,
+    /// Area density had the other subpath sampled it; zero until known.
+    pdf_rev: f64,
+    /// Whether the subpath left this vertex by a specular bounce.
+    delta: bool,
+}
+
+impl<'a> Vertex<'a> {
+    fn new(kind: VertexKind<'a>, point: Vec3, normal: Vec3, beta: Color, pdf_fwd: f64) -> Vertex<'a> {
+        Vertex {
+            kind,
+            point,
+            normal,
+            beta,
+            pdf_fwd,
+            pdf_rev: 0.0,
+            delta: false,
+        }
+    }
+    fn has_surface(&self) -> bool {
+        self.normal.dot(&self.normal) > 0.0
+    }
+    /// Cosine between the normal and the direction to `other`; 1 without a surface.
+    fn cos_to(&self, other: &Vertex) -> f64 {
+        if !self.has_surface() {
+            return 1.0;
+        }
+        (other.point - self.point).normalize().dot(&self.normal).abs()
+    }
+    /// Converts a solid angle density of leaving towards `next` into an area
+    /// density at `next`.
+    fn to_area(&self, pdf: f64, next: &Vertex) -> f64 {
+        let offset = next.point - self.point;
+        let distance_squared = offset.dot(&offset);
+        if distance_squared == 0.0 {
+            return 0.0;
+        }
+        pdf * next.cos_to(self) / distance_squared
+    }
+    /// Area density with which this vertex sends a path arriving from `previous` on to `next`.
+    fn pdf(&self, scene: &Scene, previous: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = (next.point - self.point).normalize();
+        let pdf = match (&self.kind, previous) {
+            (VertexKind::Light(index, hit), _) => scene.emission_pdf(*index, &self.point, hit.as_ref(), &direction).1,
+            (VertexKind::Surface { hit, object, .. }, Some(previous)) =>
+                object.material.pdf(&(previous.point - self.point).normalize(), &direction, hit),
+            _ => 0.0,
+        };
+        self.to_area(pdf, next)
+    }
+}
+
+/// Whether the light at `index` reaches `object` at `point`, as restricted by
+/// light linking and cutoff radii. Emissive objects light everything.
+fn lights(scene: &Scene, index: LightIndex, object: &Object, point: &Vec3) -> bool {
+    match index {
+        LightIndex::Light(i) => scene.lights()[i].illuminates(object) && scene.lights()[i].reaches(point),
+        LightIndex::Emitter(_) => true,
+    }
+}
+
+/// Lights met by `ray` before `nearest`, which the last vertex of the camera
+/// subpath `path` chose with solid angle density `pdf`.
+fn light_hits(scene: &Scene, ray: &Ray, nearest: f64, path: &[Vertex], pdf: f64) -> Color {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    let previous = path.last().unwrap();
+    let direction = ray.direction.normalize();
+    for (i, light) in scene.lights().iter().enumerate() {
+        let light_hit = match light.hit(ray) {
+            Some(light_hit) if nearest.is_infinite() || light_hit.distance < nearest => light_hit,
+            _ => continue,
+        };
+        if let VertexKind::Surface { object, .. } = &previous.kind {
+            if !lights(scene, LightIndex::Light(i), object, &previous.point) {
+                continue;
+            }
+        }
+        let weight = if light.is_infinite() {
+            match &previous.kind {
+                VertexKind::Surface { hit, .. } if !previous.delta =>
+                    sampling::power_heuristic(pdf, light.pdf(hit, &direction)),
+                _ => 1.0,
+            }
+        } else {
+            let point = ray.origin + direction * light_hit.distance;
+            let mut vertex = Vertex::new(VertexKind::Light(LightIndex::Light(i), None), point, light_hit.normal, Color::new(1.0, 1.0, 1.0), 0.0);
+            vertex.pdf_fwd = previous.to_area(pdf, &vertex);
+            let full: Vec<&Vertex> = std::iter::once(&vertex).chain(path.iter().rev()).collect();
+            mis_weight(scene, &full, 0)
+        };
+        color += light_hit.radiance * weight;
+    }
+    color
+}
+
+/// Light emitted towards the camera subpath `path` by the surface at `vertex`.
+fn emission_hit(scene: &Scene, path: &[Vertex], vertex: &Vertex) -> Color {
+    let VertexKind::Surface { hit, object, wo } = &vertex.kind else {
+        return Color::new(0.0, 0.0, 0.0);
+    };
+    if !object.material.is_emissive() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let emitted = object.material.emitted(wo, hit);
+    if emitted.is_black() {
+        return emitted;
+    }
+    let index = match scene.objects().iter().position(|other| std::ptr::eq(other, *object)) {
+        Some(index) => LightIndex::Emitter(index),
+        None => return emitted,
+    };
+    let mut light = Vertex::new(VertexKind::Light(index, Some(hit.clone())), vertex.point, vertex.normal, vertex.beta, vertex.pdf_fwd);
+    light.pdf_rev = vertex.pdf_rev;
+    let full: Vec<&Vertex> = std::iter::once(&light).chain(path.iter().rev()).collect();
+    emitted * mis_weight(scene, &full, 0)
+}
+
+/// Lights at infinity sampled directly at a camera subpath vertex, weighted
+/// against reaching them by BSDF sampling.
+fn infinite_lights(scene: &Scene, vertex: &Vertex) -> Color {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    let VertexKind::Surface { hit, object, wo } = &vertex.kind else {
+        return color;
+    };
+    for (i, light) in scene.lights().iter().enumerate() {
+        if !light.is_infinite() || !lights(scene, LightIndex::Light(i), object, &hit.point) {
+            continue;
+        }
+        let light_sample = light.sample(hit);
+        if light_sample.radiance.is_black() {
+            continue;
+        }
+        let cos = light_sample.direction.dot(&hit.normal).abs();
+        let mut light_color = vertex.beta * object.material.eval(wo, &light_sample.direction, hit) * light_sample.radiance * (cos / light_sample.pdf);
+        if light_color.is_black() {
+            continue;
+        }
+        if !light.is_delta() {
+            let bsdf_pdf = object.material.pdf(wo, &light_sample.direction, hit);
+            light_color = light_color * sampling::power_heuristic(light_sample.pdf, bsdf_pdf);
+        }
+        color += light_color * object.shadow(hit, &light_sample, scene.objects(), Occlusion::Exact);
+    }
+    color
+}
+
+/// Light carried by the path joining the end of the light subpath `light` to
+/// the end of the camera subpath `camera`.
+fn connect(scene: &Scene, light: &[Vertex], camera: &[Vertex]) -> Color {
+    let (y, z) = (light.last().unwrap(), camera.last().unwrap());
+    let VertexKind::Surface { hit, object, wo } = &z.kind else {
+        return Color::new(0.0, 0.0, 0.0);
+    };
+    if let VertexKind::Light(index, _) = &y.kind {
+        if !lights(scene, *index, object, &z.point) {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+    }
+    let to_light = y.point - z.point;
+    let distance = to_light.length();
+    if distance <= CONNECTION_OFFSET {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let direction = to_light.scale(1.0 / distance);
+    let light_end = match &y.kind {
+        VertexKind::Light(index, light_hit) => y.beta * scene.emission(*index, &y.point, light_hit.as_ref(), &-direction),
+        VertexKind::Surface { hit, object, wo } => y.beta * object.material.eval(&-direction, wo, hit),
+        VertexKind::Camera => return Color::new(0.0, 0.0, 0.0),
+    };
+    let geometry_term = y.cos_to(z) * z.cos_to(y) / (distance * distance);
+    let color = z.beta * object.material.eval(wo, &direction, hit) * light_end * geometry_term;
+    if color.is_black() {
+        return color;
+    }
+    let connection = LightSample {
+        direction,
+        distance: distance - CONNECTION_OFFSET,
+        radiance: Color::new(1.0, 1.0, 1.0),
+        pdf: 1.0,
+    };
+    let transmittance = object.shadow(hit, &connection, scene.objects(), Occlusion::Exact);
+    if transmittance.is_black() {
+        return transmittance;
+    }
+    let full: Vec<&Vertex> = light.iter().chain(camera.iter().rev()).collect();
+    color * transmittance * mis_weight(scene, &full, light.len())
+}
+
+/// Power heuristic weight of the path `full`, listed from the light to the
+/// camera, as sampled with its first `s` vertices on the light subpath,
+/// against every other split that could have sampled it.
+fn mis_weight(scene: &Scene, full: &[&Vertex], s: usize) -> f64 {
+    let k = full.len() - 1;
+    // Densities of each vertex when sampled from the light and from the camera.
+    let mut from_light = Vec::with_capacity(full.len());
+    let mut from_camera = Vec::with_capacity(full.len());
+    let mut delta: Vec<bool> = full.iter().map(|vertex| vertex.delta).collect();
+    for (i, vertex) in full.iter().enumerate() {
+        if i < s {
+            from_light.push(vertex.pdf_fwd);
+            from_camera.push(vertex.pdf_rev);
+        } else {
+            from_camera.push(vertex.pdf_fwd);
+            from_light.push(vertex.pdf_rev);
+        }
+    }
+    let VertexKind::Light(index, ref hit) = full[0].kind else {
+        return 0.0;
+    };
+    if s == 0 {
+        let (pdf_position, _) = scene.emission_pdf(index, &full[0].point, hit.as_ref(), &full[0].normal);
+        from_light[0] = scene.light_sampler().pick_probability(index) * pdf_position;
+        from_light[1] = full[0].pdf(scene, None, full[1]);
+        delta[0] = false;
+    } else {
+        let (y, z) = (full[s - 1], full[s]);
+        from_camera[s - 1] = z.pdf(scene, Some(full[s + 1]), y);
+        if s >= 2 {
+            from_camera[s - 2] = y.pdf(scene, Some(z), full[s - 2]);
+        }
+        from_light[s] = y.pdf(scene, if s >= 2 { Some(full[s - 2]) } else { None }, z);
+        from_light[s + 1] = z.pdf(scene, Some(y), full[s + 1]);
+        delta[s - 1] = false;
+        delta[s] = false;
+    }
+    let delta_light = match index {
+        LightIndex::Light(i) => scene.lights()[i].is_delta(),
+        LightIndex::Emitter(_) => false,
+    };
+    let starts_paths = from_light[0] > 0.0;
+    // Splits ending on the camera lens (s' = k) are never traced.
+    let valid = |split: usize| match split {
+        0 => !delta_light,
+        _ => starts_paths && split < k && !delta[split - 1] && !delta[split],
+    };
+    let remap = |pdf: f64| if pdf == 0.0 { 1.0 } else { pdf };
+    let mut sum = 1.0;
+    let mut ratio = 1.0;
+    for split in (0..s).rev() {
+        ratio *= remap(from_camera[split]) / remap(from_light[split]);
+        if valid(split) {
+            sum += ratio * ratio;
+        }
+    }
+    ratio = 1.0;
+    for split in s + 1..k {
+        ratio *= remap(from_light[split - 1]) / remap(from_camera[split - 1]);
+        if valid(split) {
+            sum += ratio * ratio;
+        }
+    }
+    1.0 / sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        geometry::{sphere::Sphere, plane::Plane},
+        material::{lambertian::Lambertian, dielectric::Dielectric, emissive::Emissive, subsurface::Subsurface},
+        light::{point_source::PointSource, area::DiskLight, linking::{LinkedLight, LightLinking}},
+        integrator::{self, path::PathTracer},
+    };
+
+    fn average(samples: u32, mut estimate: impl FnMut() -> f64) -> f64 {
+        (0..samples).map(|_| estimate()).sum::<f64>() / samples as f64
+    }
+
+    #[test]
+    fn test_converges_to_interreflected_radiance() {
+        // Inside a unit sphere of albedo 0.5 around a unit point light, all
+        // bounces together give a E / (π (1 - a)) = 1 / π.
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        ));
+        scene.add_light(Box::new(PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.3, -0.2, 1.0));
+        let direct = average(1000, || Bidirectional::new(0).radiance(&scene, ray).red);
+        assert_approx_eq!(direct, 0.5 / PI, 1e-6);
+        let all = average(20_000, || Bidirectional::new(50).radiance(&scene, ray).red);
+        assert_approx_eq!(all, 1.0 / PI, 0.01);
+    }
+
+    #[test]
+    fn test_matches_path_tracing_with_area_light() {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+            Box::new(Sphere::new(Vec3::new(0.6, 0.5, 0.4), 0.5)),
+        ));
+        scene.add_light(Box::new(DiskLight::new(
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            0.5,
+            Color::new(1.0, 1.0, 1.0),
+            4.0,
+        )));
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, -1.0, 1.0));
+        let samples = 40_000;
+        let path = average(samples, || PathTracer::new(4).radiance(&scene, ray).red);
+        let bidirectional = average(samples, || Bidirectional::new(4).radiance(&scene, ray).red);
+        assert!((bidirectional / path - 1.0).abs() < 0.03, "{} vs {}", bidirectional, path);
+    }
+
+    #[test]
+    fn test_linked_light_matches_path_tracing() {
+        // The light reaches the floor only after bouncing off the ball, which
+        // gives a fraction of what lighting the floor directly would.
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ).with_name("floor"));
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+            Box::new(Sphere::new(Vec3::new(0.6, 0.5, 0.4), 0.5)),
+        ).with_name("ball"));
+        let disk = DiskLight::new(Vec3::new(-1.5, 0.6, 0.4), Vec3::new(1.0, 0.0, 0.0), 0.5, Color::new(1.0, 1.0, 1.0), 4.0);
+        scene.add_light(Box::new(LinkedLight::new(Box::new(disk), LightLinking::include(&["ball"]))));
+        let ray = Ray::new(Vec3::new(-0.2, 1.5, -1.0), Vec3::new(0.0, -1.5, 1.4));
+        let samples = 100_000;
+        let path = average(samples, || PathTracer::new(4).radiance(&scene, ray).red);
+        let bidirectional = average(samples, || Bidirectional::new(4).radiance(&scene, ray).red);
+        let unlinked = average(samples, || Bidirectional::new(0).radiance(&scene, ray).red);
+        assert_eq!(unlinked, 0.0);
+        assert!((bidirectional / path - 1.0).abs() < 0.1, "{} vs {}", bidirectional, path);
+    }
+
+    #[test]
+    fn test_subsurface_matches_path_tracing() {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Subsurface::from_albedo(Color::new(0.8, 0.8, 0.8), Color::new(0.5, 0.5, 0.5), 1.3)),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        ));
+        scene.add_light(Box::new(PointSource::new(Vec3::new(2.0, 2.0, -2.0), Color::new(1.0, 1.0, 1.0), 10.0)));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        let samples = 20_000;
+        let path = average(samples, || PathTracer::new(4).radiance(&scene, ray).red);
+        let bidirectional = average(samples, || Bidirectional::new(4).radiance(&scene, ray).red);
+        assert!(path > 0.0);
+        assert!((bidirectional / path - 1.0).abs() < 0.05, "{} vs {}", bidirectional, path);
+    }
+
+    #[test]
+    fn test_hidden_objects_are_path_traced() {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        let mut integrator = Bidirectional::new(4);
+        integrator.begin_pass(&scene, 0);
+        assert!(!integrator.path_traced);
+        scene.add_object(Object::default().with_visibility(Visibility { camera: false, ..Visibility::default() }));
+        integrator.begin_pass(&scene, 0);
+        assert!(integrator.path_traced);
+    }
+
+    /// Floor with a glass ball focusing a large emitter onto it. Following
+    /// BSDF samples alone finds the caustic too, only more slowly.
+    fn caustic_scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        scene.add_object(Object::new(
+            Box::new(Dielectric::new(1.5, 0.0)),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.8, 0.0), 0.5)),
+        ));
+        scene.add_object(Object::new(
+            Box::new(Emissive::new(Color::new(1.0, 1.0, 1.0), 2.0)),
+            Box::new(Sphere::new(Vec3::new(0.0, 3.0, 0.0), 1.0)),
+        ));
+        scene
+    }
+
+    #[test]
+    fn test_caustic_matches_bsdf_sampling() {
+        let scene = caustic_scene();
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.0, -1.0, 2.0));
+        let samples = 40_000;
+        let bidirectional = average(samples, || Bidirectional::new(6).radiance(&scene, ray).red);
+        let bsdf_only = average(samples, || integrator::bsdf_sampled(&scene, ray, 7).red);
+        assert!((bidirectional / bsdf_only - 1.0).abs() < 0.03, "{} vs {}", bidirectional, bsdf_only);
+    }
+
+    #[test]
+    fn test_path_tracing_caustic_matches_bsdf_sampling() {
+        let scene = caustic_scene();
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.0, -1.0, 2.0));
+        let samples = 40_000;
+        let path = average(samples, || PathTracer::new(6).radiance(&scene, ray).red);
+        let bsdf_only = average(samples, || integrator::bsdf_sampled(&scene, ray, 7).red);
+        assert!((path / bsdf_only - 1.0).abs() < 0.03, "{} vs {}", path, bsdf_only);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    integrator::Integrator,
+    scene::Scene,
+    ray::Ray,
+    image::Color,
+};
+
+/// Distance shown as white by `DebugMode::Depth` when chosen by name.
+const DEFAULT_MAX_DISTANCE: f64 = 10.0;
+
+/// Surface quantity shown by the debug integrator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugMode {
+    /// World-space normal mapped from `[-1, 1]` to `[0, 1]`.
+    Normals,
+    /// Hit distance, from black at the camera to white at `max_distance`.
+    Depth { max_distance: f64 },
+    /// Texture coordinates in red and green.
+    Uv,
+    /// Barycentric coordinates of the first, second and third vertex in red,
+    /// green and blue.
+    Barycentrics,
+}
+
+impl DebugMode {
+    /// `normals`, `uv`, `barycentrics`, `depth`, or `depth:<max_distance>` for another
+    /// distance than the default.
+    pub fn from_name(name: &str) -> Option<DebugMode> {
+        match name {
+            "normals" => Some(DebugMode::Normals),
+            "depth" => Some(DebugMode::Depth { max_distance: DEFAULT_MAX_DISTANCE }),
+            "uv" => Some(DebugMode::Uv),
+            "barycentrics" => Some(DebugMode::Barycentrics),
+            _ => Some(DebugMode::Depth { max_distance: name.strip_prefix("depth:")?.parse().ok()? }),
+        }
+    }
+}
+
+/// Shows a surface quantity at the first hit instead of light transport.
+/// Misses are black.
+pub struct DebugIntegrator {
+    pub mode: DebugMode,
+}
+
+impl DebugIntegrator {
+    pub fn new(mode: DebugMode) -> DebugIntegrator {
+        DebugIntegrator {
+            mode,
+        }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        let hit = match scene.intersect(&ray) {
+            Some((hit, _)) => hit,
+            None => return Color::new(0.0, 0.0, 0.0),
+        };
+        match self.mode {
+            DebugMode::Normals => Color::new(
+                hit.normal.x * 0.5 + 0.5,
+                hit.normal.y * 0.5 + 0.5,
+                hit.normal.z * 0.5 + 0.5,
+            ),
+            DebugMode::Depth { max_distance } => {
+                let depth = (hit.distance / max_distance).clamp(0.0, 1.0);
+                Color::new(depth, depth, depth)
+            }
+            DebugMode::Uv => Color::new(hit.uv.0, hit.uv.1, 0.0),
+            DebugMode::Barycentrics => {
+                let (b1, b2) = hit.barycentrics;
+                Color::new(1.0 - b1 - b2, b1, b2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        vec3::Vec3,
+        object::Object,
+        material,
+        geometry::plane::Plane,
+    };
+
+    #[test]
+    fn test_modes_show_first_hit() {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(material::default(), Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0))));
+        let ray = Ray::new(Vec3::new(0.3, 2.0, 0.7), Vec3::new(0.0, -1.0, 0.0));
+        let normal = DebugIntegrator::new(DebugMode::Normals).radiance(&scene, ray);
+        assert_approx_eq!(normal.red, 0.5);
+        assert_approx_eq!(normal.green, 1.0);
+        assert_approx_eq!(normal.blue, 0.5);
+        let depth = DebugIntegrator::new(DebugMode::from_name("depth:8").unwrap()).radiance(&scene, ray);
+        assert_approx_eq!(depth.red, 0.25);
+        let (hit, _) = scene.intersect(&ray).unwrap();
+        let uv = DebugIntegrator::new(DebugMode::Uv).radiance(&scene, ray);
+        assert_approx_eq!(uv.red, hit.uv.0);
+        assert_approx_eq!(uv.green, hit.uv.1);
+        let barycentrics = DebugIntegrator::new(DebugMode::from_name("barycentrics").unwrap()).radiance(&scene, ray);
+        assert_approx_eq!(barycentrics.red + barycentrics.green + barycentrics.blue, 1.0);
+        assert_approx_eq!(barycentrics.green, hit.barycentrics.0);
+        let miss = Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(DebugIntegrator::new(DebugMode::Normals).radiance(&scene, miss).is_black());
+    }
+}
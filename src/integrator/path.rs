@@ -0,0 +1,257 @@
+use crate::{
+    integrator::Integrator,
+    scene::Scene,
+    object::{Object, Occlusion},
+    ray::{Ray, RayKind},
+    image::Color,
+    light::sampler::LightIndex,
+    geometry::Hit,
+    vec3::Vec3,
+    sampling,
+};
+
+/// Bounces traced before paths may be ended by Russian roulette.
+const ROULETTE_DEPTH: u32 = 3;
+/// Highest probability of continuing a path, so bright paths still end.
+const MAX_SURVIVAL: f64 = 0.95;
+/// Step taken off a surface before tracing the next bounce from it.
+const BOUNCE_OFFSET: f64 = 1e-6;
+
+/// Unidirectional path tracer following BSDF-sampled bounces up to
+/// `max_depth`. At every vertex lights are sampled directly and the bounce
+/// ray may hit them too; the two are combined with multiple importance sampling.
+/// Glass blocks direct light, which reaches surfaces behind it only by
+/// refraction, so it is counted once.
+pub struct PathTracer {
+    pub max_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: u32) -> PathTracer {
+        PathTracer {
+            max_depth,
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        let depth = self.max_depth;
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = ray;
+        // Vertex the ray left and its BSDF sampling density, unless it was
+        // the camera or a specular bounce that lights cannot be sampled for.
+        let mut previous: Option<(Hit, &Object, f64)> = None;
+        for bounce in 0..=depth {
+            let hit_object = scene.intersect(&ray);
+            let nearest = hit_object.as_ref().map_or(f64::INFINITY, |(hit, _)| hit.distance);
+            color += throughput * light_hits(scene, &ray, nearest, previous.as_ref());
+            let (hit, object) = match hit_object {
+                Some(hit_object) => hit_object,
+                None => break,
+            };
+            let wo = -ray.direction.normalize();
+            color += throughput * emission(scene, &wo, &hit, object, previous.as_ref());
+            color += throughput * object.direct(&ray, &hit, scene, bounce < depth, Occlusion::Exact, depth - bounce);
+            if bounce == depth {
+                break;
+            }
+            let sample = match object.material.sample(&wo, &hit) {
+                Some(sample) if sample.pdf > 0.0 => sample,
+                _ => break,
+            };
+            throughput = throughput * sample.value * (sample.direction.dot(&hit.normal).abs() / sample.pdf);
+            if throughput.is_black() {
+                break;
+            }
+            if bounce >= ROULETTE_DEPTH {
+                let survival = throughput.red.max(throughput.green).max(throughput.blue).min(MAX_SURVIVAL);
+                if rand::random::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+            let side = if sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+            let origin = hit.point + hit.normal * (BOUNCE_OFFSET * side);
+            ray = Ray::new(origin, sample.direction).with_kind(RayKind::Reflection);
+            previous = if sample.specular { None } else { Some((hit, object, sample.pdf)) };
+        }
+        color
+    }
+}
+
+/// Lights met by `ray` before `nearest`, weighted against having sampled
+/// them directly from the `previous` vertex.
+fn light_hits(scene: &Scene, ray: &Ray, nearest: f64, previous: Option<&(Hit, &Object, f64)>) -> Color {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    let direction = ray.direction.normalize();
+    for (i, light) in scene.lights().iter().enumerate() {
+        let light_hit = match light.hit(ray) {
+            Some(light_hit) if nearest.is_infinite() || light_hit.distance < nearest => light_hit,
+            _ => continue,
+        };
+        let weight = match previous {
+            None => 1.0,
+            Some((hit, object, pdf)) => {
+                if !light.illuminates(object) || !light.reaches(&hit.point) {
+                    continue;
+                }
+                let samples = scene.light_sampler().expected_samples(LightIndex::Light(i));
+                sampling::power_heuristic(*pdf, samples * light.pdf(hit, &direction))
+            }
+        };
+        color += light_hit.radiance * weight;
+    }
+    color
+}
+
+/// Light emitted by `object` at `hit` towards `wo`, weighted against
+/// having sampled it directly from the `previous` vertex.
+fn emission(scene: &Scene, wo: &Vec3, hit: &Hit, object: &Object, previous: Option<&(Hit, &Object, f64)>) -> Color {
+    if !object.material.is_emissive() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let emitted = object.material.emitted(wo, hit);
+    let pdf = match previous {
+        Some((_, from, pdf)) if !std::ptr::eq(*from, object) => *pdf,
+        _ => return emitted,
+    };
+    let index = match scene.objects().iter().position(|other| std::ptr::eq(other, object)) {
+        Some(index) => index,
+        None => return emitted,
+    };
+    let cos_light = wo.dot(&hit.normal).abs();
+    if cos_light <= 0.0 {
+        return emitted;
+    }
+    let samples = scene.light_sampler().expected_samples(LightIndex::Emitter(index));
+    let light_pdf = object.geometry.pdf(hit) * hit.distance * hit.distance / cos_light;
+    emitted * sampling::power_heuristic(pdf, samples * light_pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        geometry::{sphere::Sphere, plane::Plane},
+        material::{lambertian::Lambertian, dielectric::Dielectric},
+        integrator,
+        light::{Light, point_source::PointSource, area::DiskLight, environment::EnvironmentLight},
+        texture::image_texture::ImageTexture,
+    };
+
+    /// Average radiance seen from the centre of a unit sphere of albedo 0.5
+    /// lit by a unit point light at its centre.
+    fn inside_sphere(depth: u32) -> f64 {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        ));
+        scene.add_light(Box::new(PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)));
+        let samples = 20_000;
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.3, -0.2, 1.0));
+            sum += PathTracer::new(depth).radiance(&scene, ray).red;
+        }
+        sum / samples as f64
+    }
+
+    #[test]
+    fn test_path_tracing_converges_to_interreflected_radiance() {
+        // Direct light alone gives a E / π; every bounce adds another factor
+        // of a, summing to a E / (π (1 - a)).
+        assert_approx_eq!(inside_sphere(0), 0.5 / PI, 1e-6);
+        assert_approx_eq!(inside_sphere(50), 1.0 / PI, 0.01);
+    }
+
+    /// Camera ray hitting a diffuse floor of albedo 0.5 at the origin.
+    fn floor_scene(light: Box<dyn Light>) -> (Scene, Ray) {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        scene.add_light(light);
+        (scene, Ray::new(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, -1.0, 1.0)))
+    }
+
+    fn average(samples: u32, mut estimate: impl FnMut() -> f64) -> f64 {
+        (0..samples).map(|_| estimate()).sum::<f64>() / samples as f64
+    }
+
+    /// Light sampling, BSDF sampling and their combination, as seen along the camera ray.
+    fn strategies(light: Box<dyn Light>) -> [f64; 3] {
+        let (scene, ray) = floor_scene(light);
+        let samples = 40_000;
+        let light_only = average(samples, || PathTracer::new(0).radiance(&scene, ray).red);
+        let combined = average(samples, || PathTracer::new(1).radiance(&scene, ray).red);
+        let bsdf_only = average(samples, || {
+            let (hit, object) = scene.intersect(&ray).unwrap();
+            let wo = -ray.direction.normalize();
+            let sample = object.material.sample(&wo, &hit).unwrap();
+            let bounce = Ray::new(hit.point, sample.direction);
+            match scene.lights()[0].hit(&bounce) {
+                Some(light_hit) => {
+                    let cos = sample.direction.dot(&hit.normal).abs();
+                    (light_hit.radiance * sample.value).red * cos / sample.pdf
+                }
+                None => 0.0,
+            }
+        });
+        [light_only, bsdf_only, combined]
+    }
+
+    #[test]
+    fn test_mis_matches_strategies_for_area_light() {
+        // A facing disk of radius r at height h: L r² / (h² + r²) times the albedo.
+        let disk = DiskLight::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0);
+        for estimate in strategies(Box::new(disk)) {
+            assert_approx_eq!(estimate, 0.5 / 5.0, 0.005);
+        }
+    }
+
+    /// Map whose upper row covers the sky above the floor with an average
+    /// radiance of 1 and whose lower row lies below it.
+    fn uneven_map() -> ImageTexture {
+        let texels = [2.0, 0.0, 0.5, 1.5, 1.0, 3.0, 1.0, 0.5];
+        ImageTexture::new(4, 2, texels.iter().map(|&value| Color::new(value, value, value)).collect())
+    }
+
+    #[test]
+    fn test_mis_matches_strategies_for_environment_light() {
+        // Each column of the sky gets the same cosine-weighted share, so the
+        // floor sees the row's average radiance.
+        for estimate in strategies(Box::new(EnvironmentLight::new(uneven_map(), 1.0, 0.3))) {
+            assert_approx_eq!(estimate, 0.5, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_mis_through_glass_matches_bsdf_sampling() {
+        // Sky seen through a glass ball reaches the floor beneath it only by
+        // refraction, never also as a light sample.
+        let (mut scene, ray) = floor_scene(Box::new(EnvironmentLight::new(uneven_map(), 1.0, 0.3)));
+        scene.add_object(Object::new(
+            Box::new(Dielectric::new(1.5, 0.0)),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.6, 0.0), 0.4)),
+        ));
+        let samples = 40_000;
+        let path = average(samples, || PathTracer::new(6).radiance(&scene, ray).red);
+        let bsdf_only = average(samples, || integrator::bsdf_sampled(&scene, ray, 6).red);
+        assert!((path / bsdf_only - 1.0).abs() < 0.03, "{} vs {}", path, bsdf_only);
+    }
+
+    #[test]
+    fn test_mis_matches_light_sampling_for_point_light() {
+        let point = PointSource::new(Vec3::new(0.0, 2.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0);
+        let [light_only, bsdf_only, combined] = strategies(Box::new(point));
+        assert_approx_eq!(light_only, 0.5 * 0.25 / PI, 1e-9);
+        assert_approx_eq!(combined, light_only, 1e-9);
+        assert_approx_eq!(bsdf_only, 0.0);
+    }
+}
@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+use crate::{
+    integrator::Integrator,
+    scene::Scene,
+    object::{Object, Occlusion},
+    ray::{Ray, RayKind},
+    image::Color,
+    light::sampler::LightIndex,
+    geometry::Hit,
+    kd_tree::KdTree,
+    vec3::Vec3,
+};
+
+/// Bounces traced before camera paths may be ended by Russian roulette.
+const ROULETTE_DEPTH: u32 = 3;
+/// Highest probability of continuing a path, so bright paths still end.
+const MAX_SURVIVAL: f64 = 0.95;
+/// Step taken off a surface before tracing the next bounce from it.
+const BOUNCE_OFFSET: f64 = 1e-6;
+/// Share of the photons kept from one pass to the next by default, as the
+/// gather radius shrinks.
+const DEFAULT_ALPHA: f64 = 2.0 / 3.0;
+/// Lowest cosine between a photon's normal and the gathering surface's for
+/// the photon to count, so photons on nearby surfaces facing elsewhere do not.
+const NORMAL_AGREEMENT: f64 = 0.9;
+
+/// Light that has reached a surface through one or more specular bounces.
+#[derive(Debug, Clone)]
+pub struct Photon {
+    /// Direction back towards where the photon came from.
+    pub direction: Vec3,
+    pub normal: Vec3,
+    pub power: Color,
+}
+
+/// Path tracer taking caustics, light focused onto surfaces by glass, water
+/// or mirrors, from a photon map. Before each pass, photons are emitted from
+/// the lights and emissive objects and stored where they land after specular
+/// bounces; rendering estimates their density around every hit. The gather
+/// radius shrinks from pass to pass as in progressive photon mapping, so the
+/// blur of the estimate fades as passes accumulate. Direct light is sampled
+/// with glass casting full shadows. Lights at infinity emit no photons, so
+/// their caustics are only found by following bounces. Light linking is not
+/// applied to photons.
+pub struct PhotonMapper {
+    pub max_depth: u32,
+    /// Photons emitted before each pass.
+    pub photons: u32,
+    /// Gather radius of the first pass.
+    pub radius: f64,
+    /// Share of the photons kept as the radius shrinks after each pass,
+    /// between 0 and 1; smaller values shrink it faster.
+    pub alpha: f64,
+    pass_radius: f64,
+    map: KdTree<Photon>,
+}
+
+impl PhotonMapper {
+    pub fn new(max_depth: u32, photons: u32, radius: f64) -> PhotonMapper {
+        PhotonMapper {
+            max_depth,
+            photons,
+            radius,
+            alpha: DEFAULT_ALPHA,
+            pass_radius: radius,
+            map: KdTree::new(Vec::new()),
+        }
+    }
+    pub fn with_alpha(mut self, alpha: f64) -> PhotonMapper {
+        self.alpha = alpha;
+        self
+    }
+    /// Gather radius of the current pass.
+    pub fn pass_radius(&self) -> f64 {
+        self.pass_radius
+    }
+    /// Photons stored for the current pass.
+    pub fn map(&self) -> &KdTree<Photon> {
+        &self.map
+    }
+    /// Emits `photons` photons from lights drawn by power, storing them at
+    /// each non-specular surface they reach by specular bounces until one
+    /// scatters diffusely.
+    fn trace_photons(&self, scene: &Scene) -> KdTree<Photon> {
+        let mut stored = Vec::new();
+        for _ in 0..self.photons {
+            let (index, probability) = match scene.light_sampler().pick() {
+                Some(pick) => pick,
+                None => break,
+            };
+            let (emission, _) = match scene.sample_emission(index) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            if emission.pdf_position <= 0.0 || emission.pdf_direction <= 0.0 || emission.radiance.is_black() {
+                continue;
+            }
+            let has_surface = emission.normal.dot(&emission.normal) > 0.0;
+            let cos = if has_surface { emission.direction.dot(&emission.normal).abs() } else { 1.0 };
+            let pdf = probability * emission.pdf_position * emission.pdf_direction * self.photons as f64;
+            let mut power = emission.radiance * (cos / pdf);
+            let side = if emission.direction.dot(&emission.normal) < 0.0 { -1.0 } else { 1.0 };
+            let mut ray = Ray::new(emission.point + emission.normal * (BOUNCE_OFFSET * side), emission.direction)
+                .with_kind(RayKind::Reflection);
+            for bounce in 0..=self.max_depth {
+                let (hit, object) = match scene.intersect(&ray) {
+                    Some(hit_object) => hit_object,
+                    None => break,
+                };
+                let wo = -ray.direction.normalize();
+                if bounce > 0 && !object.material.is_specular() {
+                    stored.push((hit.point, Photon { direction: wo, normal: hit.normal, power }));
+                }
+                if bounce == self.max_depth {
+                    break;
+                }
+                let sample = match object.material.sample(&wo, &hit) {
+                    Some(sample) if sample.specular && sample.pdf > 0.0 => sample,
+                    _ => break,
+                };
+                let cos = sample.direction.dot(&hit.normal).abs();
+                power = power * sample.value * (cos * sample.eta * sample.eta / sample.pdf);
+                if power.is_black() {
+                    break;
+                }
+                let side = if sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+                ray = Ray::new(hit.point + hit.normal * (BOUNCE_OFFSET * side), sample.direction)
+                    .with_kind(RayKind::Reflection);
+            }
+        }
+        KdTree::new(stored)
+    }
+    /// Light from the photons around `hit` scattered towards `wo`.
+    fn gather(&self, hit: &Hit, object: &Object, wo: &Vec3) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        self.map.within(&hit.point, self.pass_radius, |_, photon| {
+            if photon.normal.dot(&hit.normal) >= NORMAL_AGREEMENT {
+                color += object.material.eval(wo, &photon.direction, hit) * photon.power;
+            }
+        });
+        color / (PI * self.pass_radius * self.pass_radius)
+    }
+}
+
+impl Integrator for PhotonMapper {
+    fn begin_pass(&mut self, scene: &Scene, pass: u32) {
+        // Each pass keeps alpha of the photons newly added to the estimate,
+        // which shrinks the area r² by (i + alpha) / (i + 1) after pass i.
+        let mut radius_squared = self.radius * self.radius;
+        for i in 1..=pass {
+            radius_squared *= (i as f64 + self.alpha) / (i as f64 + 1.0);
+        }
+        self.pass_radius = radius_squared.sqrt();
+        self.map = self.trace_photons(scene);
+    }
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = ray;
+        // Whether the ray left the camera or a specular bounce, and whether
+        // any bounce before it scattered diffusely, in which case lights it
+        // meets were already found by photons.
+        let mut specular = true;
+        let mut diffuse = false;
+        for bounce in 0..=self.max_depth {
+            let hit_object = scene.intersect(&ray);
+            if specular {
+                let nearest = hit_object.as_ref().map_or(f64::INFINITY, |(hit, _)| hit.distance);
+                color += throughput * light_hits(scene, &ray, nearest, diffuse);
+            }
+            let (hit, object) = match hit_object {
+                Some(hit_object) => hit_object,
+                None => break,
+            };
+            let wo = -ray.direction.normalize();
+            color += throughput * emission(scene, &wo, &hit, object, specular && !diffuse);
+            color += throughput * object.direct(&ray, &hit, scene, false, Occlusion::Exact, self.max_depth - bounce);
+            color += throughput * self.gather(&hit, object, &wo);
+            if bounce == self.max_depth {
+                break;
+            }
+            let sample = match object.material.sample(&wo, &hit) {
+                Some(sample) if sample.pdf > 0.0 => sample,
+                _ => break,
+            };
+            throughput = throughput * sample.value * (sample.direction.dot(&hit.normal).abs() / sample.pdf);
+            if throughput.is_black() {
+                break;
+            }
+            if bounce >= ROULETTE_DEPTH {
+                let survival = throughput.red.max(throughput.green).max(throughput.blue).min(MAX_SURVIVAL);
+                if rand::random::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+            let side = if sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+            let origin = hit.point + hit.normal * (BOUNCE_OFFSET * side);
+            ray = Ray::new(origin, sample.direction).with_kind(RayKind::Reflection);
+            specular = sample.specular;
+            diffuse = diffuse || !sample.specular;
+        }
+        color
+    }
+}
+
+/// Lights met by `ray` before `nearest` after a specular bounce. After a
+/// `diffuse` bounce, only lights at infinity count; photons carry the rest.
+fn light_hits(scene: &Scene, ray: &Ray, nearest: f64, diffuse: bool) -> Color {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    for light in scene.lights() {
+        if diffuse && !light.is_infinite() {
+            continue;
+        }
+        match light.hit(ray) {
+            Some(light_hit) if nearest.is_infinite() || light_hit.distance < nearest => color += light_hit.radiance,
+            _ => {}
+        }
+    }
+    color
+}
+
+/// Light emitted by `object` at `hit` towards `wo`. Emitters that are sampled
+/// directly and emit photons only count when `visible` straight from the
+/// camera or through specular bounces.
+fn emission(scene: &Scene, wo: &Vec3, hit: &Hit, object: &Object, visible: bool) -> Color {
+    if !object.material.is_emissive() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    if !visible {
+        let sampled = scene
+            .objects()
+            .iter()
+            .position(|other| std::ptr::eq(other, object))
+            .is_some_and(|index| scene.light_sampler().pick_probability(LightIndex::Emitter(index)) > 0.0);
+        if sampled {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+    }
+    object.material.emitted(wo, hit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::plane::Plane,
+        material::{lambertian::Lambertian, dielectric::Dielectric},
+        light::spot::SpotLight,
+    };
+
+    #[test]
+    fn test_radius_shrinks_each_pass() {
+        let scene = Scene::new();
+        let mut integrator = PhotonMapper::new(4, 0, 1.0);
+        integrator.begin_pass(&scene, 0);
+        assert_eq!(integrator.pass_radius(), 1.0);
+        integrator.begin_pass(&scene, 1);
+        assert!((integrator.pass_radius().powi(2) - (1.0 + DEFAULT_ALPHA) / 2.0).abs() < 1e-12);
+        integrator.begin_pass(&scene, 1000);
+        assert!(integrator.pass_radius() < 0.4);
+        assert!(integrator.map().is_empty());
+    }
+
+    #[test]
+    fn test_photons_are_only_stored_on_diffuse_surfaces() {
+        // Photons cross two glass surfaces on their way to the floor.
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        for height in [1.0, 1.5] {
+            scene.add_object(Object::new(
+                Box::new(Dielectric::new(1.5, 0.0)),
+                Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), height)),
+            ));
+        }
+        scene.add_light(Box::new(SpotLight::new(
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            1.0,
+            0.15,
+            0.2,
+        )));
+        let mut integrator = PhotonMapper::new(2, 2_000, 0.05);
+        integrator.begin_pass(&scene, 0);
+        assert!(!integrator.map().is_empty());
+        integrator.map().within(&Vec3::new(0.0, 1.0, 0.0), 10.0, |point, _| assert!(point.y.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_refracted_spot_light_matches_paraxial_irradiance() {
+        // A spot light at height h1 above water shines on a floor h2 below
+        // the surface. Near the axis refraction narrows the beam, so the
+        // floor receives E = I T / (h1 + h2 / η)²; seen from above, the floor
+        // is dimmed by T / η² again.
+        let (ior, intensity, albedo) = (1.33, 2.0, 0.5);
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Lambertian::new(Color::new(albedo, albedo, albedo))),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        scene.add_object(Object::new(
+            Box::new(Dielectric::new(ior, 0.0)),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 1.0)),
+        ));
+        scene.add_light(Box::new(SpotLight::new(
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            intensity,
+            0.15,
+            0.2,
+        )));
+        let mut integrator = PhotonMapper::new(1, 20_000, 0.05);
+        let ray = Ray::new(Vec3::new(0.0, 1.5, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let (passes, samples) = (20, 100);
+        let mut sum = 0.0;
+        for pass in 0..passes {
+            integrator.begin_pass(&scene, pass);
+            for _ in 0..samples {
+                sum += integrator.radiance(&scene, ray).red;
+            }
+        }
+        let estimate = sum / (passes * samples) as f64;
+        let transmittance = 1.0 - ((ior - 1.0) / (ior + 1.0)).powi(2);
+        let irradiance = intensity * transmittance / (1.0 + 1.0 / ior).powi(2);
+        let expected = albedo / PI * irradiance * transmittance / (ior * ior);
+        assert!((estimate / expected - 1.0).abs() < 0.05, "{} vs {}", estimate, expected);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    integrator::Integrator,
+    scene::Scene,
+    ray::{Ray, RayKind},
+    image::Color,
+};
+
+/// Step taken off a surface before tracing a reflected or refracted ray.
+const SPECULAR_OFFSET: f64 = 1e-6;
+
+/// Classic Whitted ray tracer: direct light at every hit, plus rays followed
+/// recursively through mirror reflection and refraction up to `max_depth`.
+/// Every specular lobe is followed, so glass spawns both a reflected and a
+/// refracted ray weighted by Fresnel, and the image is free of noise. Glossy
+/// and diffuse interreflection is left out.
+pub struct Whitted {
+    pub max_depth: u32,
+}
+
+impl Whitted {
+    pub fn new(max_depth: u32) -> Whitted {
+        Whitted {
+            max_depth,
+        }
+    }
+    fn trace(&self, scene: &Scene, ray: &Ray, depth: u32) -> Color {
+        let (hit, object) = match scene.intersect(ray) {
+            Some(hit_object) => hit_object,
+            None => return scene.background(&ray.direction),
+        };
+        let mut color = object.shade(ray, &hit, scene, depth);
+        if depth == 0 {
+            return color;
+        }
+        let wo = -ray.direction.normalize();
+        for lobe in object.material.specular_lobes(&wo, &hit) {
+            if lobe.pdf <= 0.0 {
+                continue;
+            }
+            let weight = lobe.value * (lobe.direction.dot(&hit.normal).abs() / lobe.pdf);
+            let side = if lobe.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+            let origin = hit.point + hit.normal * (SPECULAR_OFFSET * side);
+            let next = Ray::new(origin, lobe.direction).with_kind(RayKind::Reflection);
+            color += weight * self.trace(scene, &next, depth - 1);
+        }
+        color
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, scene: &Scene, ray: Ray) -> Color {
+        self.trace(scene, &ray, self.max_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        vec3::Vec3,
+        object::Object,
+        geometry::{sphere::Sphere, plane::Plane},
+        material::{conductor::Conductor, dielectric::Dielectric, emissive::Emissive},
+    };
+
+    fn emitter(center: Vec3) -> Object {
+        Object::new(
+            Box::new(Emissive::new(Color::new(1.0, 1.0, 1.0), 2.0)),
+            Box::new(Sphere::new(center, 0.5)),
+        )
+    }
+
+    #[test]
+    fn test_mirror_reflects_emitter() {
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Conductor::gold(0.0)),
+            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0)),
+        ));
+        scene.add_object(emitter(Vec3::new(0.0, 2.0, 2.0)));
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, -1.0, 1.0));
+        let (hit, mirror) = scene.intersect(&ray).unwrap();
+        let sample = mirror.material.sample(&-ray.direction.normalize(), &hit).unwrap();
+        let reflectance = sample.value.red * sample.direction.dot(&hit.normal) / sample.pdf;
+        assert_approx_eq!(Whitted::new(1).radiance(&scene, ray).red, 2.0 * reflectance, 1e-9);
+        assert_approx_eq!(Whitted::new(0).radiance(&scene, ray).red, 0.0);
+    }
+
+    #[test]
+    fn test_glass_refracts_emitter() {
+        // Straight through the centre of a glass ball, losing the Fresnel
+        // reflectance at both surfaces, plus the light reflected back and
+        // forth once inside the ball.
+        let mut scene = Scene::new();
+        scene.add_object(Object::new(
+            Box::new(Dielectric::new(1.5, 0.0)),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        ));
+        scene.add_object(emitter(Vec3::new(0.0, 0.0, 3.0)));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        let reflectance = 0.04;
+        let transmittance = 1.0 - reflectance;
+        let expected = 2.0 * transmittance * transmittance * (1.0 + reflectance * reflectance);
+        assert_approx_eq!(Whitted::new(4).radiance(&scene, ray).red, expected, 1e-9);
+        assert_approx_eq!(Whitted::new(2).radiance(&scene, ray).red, 2.0 * transmittance * transmittance, 1e-9);
+        assert_approx_eq!(Whitted::new(1).radiance(&scene, ray).red, 0.0);
+    }
+}
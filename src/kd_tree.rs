@@ -0,0 +1,113 @@
+use crate::vec3::Vec3;
+
+/// Balanced kd-tree over points carrying an item each, for finding every
+/// item within a radius of a position. The tree is implicit: each range of
+/// `points` holds its median at the middle, split along `axes` there, with
+/// the two halves on either side.
+#[derive(Debug, Clone)]
+pub struct KdTree<T> {
+    points: Vec<(Vec3, T)>,
+    axes: Vec<usize>,
+}
+
+impl<T> KdTree<T> {
+    pub fn new(mut points: Vec<(Vec3, T)>) -> KdTree<T> {
+        let mut axes = vec![0; points.len()];
+        build(&mut points, &mut axes);
+        KdTree {
+            points,
+            axes,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+    /// Calls `visit` with every point within `radius` of `center` and its item.
+    pub fn within(&self, center: &Vec3, radius: f64, mut visit: impl FnMut(&Vec3, &T)) {
+        self.search(0, self.points.len(), center, radius * radius, &mut visit);
+    }
+    fn search(&self, start: usize, end: usize, center: &Vec3, radius_squared: f64, visit: &mut impl FnMut(&Vec3, &T)) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let (point, item) = &self.points[middle];
+        let offset = *center - *point;
+        if offset.dot(&offset) <= radius_squared {
+            visit(point, item);
+        }
+        let axis = self.axes[middle];
+        let delta = coordinate(center, axis) - coordinate(point, axis);
+        let (near, far) = if delta < 0.0 { ((start, middle), (middle + 1, end)) } else { ((middle + 1, end), (start, middle)) };
+        self.search(near.0, near.1, center, radius_squared, visit);
+        if delta * delta <= radius_squared {
+            self.search(far.0, far.1, center, radius_squared, visit);
+        }
+    }
+}
+
+/// Arranges `points` into a subtree, splitting along the axis of greatest extent.
+fn build<T>(points: &mut [(Vec3, T)], axes: &mut [usize]) {
+    if points.len() <= 1 {
+        return;
+    }
+    let mut min = points[0].0;
+    let mut max = points[0].0;
+    for (point, _) in points.iter() {
+        min = Vec3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+        max = Vec3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
+    }
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let middle = points.len() / 2;
+    points.select_nth_unstable_by(middle, |a, b| coordinate(&a.0, axis).total_cmp(&coordinate(&b.0, axis)));
+    axes[middle] = axis;
+    let (lower, upper) = points.split_at_mut(middle);
+    let (lower_axes, upper_axes) = axes.split_at_mut(middle);
+    build(lower, lower_axes);
+    build(&mut upper[1..], &mut upper_axes[1..]);
+}
+
+fn coordinate(point: &Vec3, axis: usize) -> f64 {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_matches_brute_force() {
+        let points: Vec<(Vec3, usize)> = (0..2000)
+            .map(|i| (Vec3::new(rand::random(), rand::random::<f64>() * 2.0, rand::random::<f64>() * 0.1), i))
+            .collect();
+        let tree = KdTree::new(points.clone());
+        assert_eq!(tree.len(), points.len());
+        for _ in 0..50 {
+            let center = Vec3::new(rand::random(), rand::random::<f64>() * 2.0, 0.05);
+            let radius = rand::random::<f64>() * 0.2;
+            let mut found = Vec::new();
+            tree.within(&center, radius, |_, &i| found.push(i));
+            found.sort();
+            let expected: Vec<usize> = points
+                .iter()
+                .filter(|(point, _)| (*point - center).dot(&(*point - center)) <= radius * radius)
+                .map(|&(_, i)| i)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+pub mod tracer;
+pub mod ray;
+pub mod camera;
+pub mod scene;
+pub mod integrator;
+pub mod image;
+pub mod vec3;
+pub mod frame;
+pub mod sampling;
+pub mod kd_tree;
+pub mod light;
+pub mod object;
+pub mod material;
+pub mod texture;
+pub mod geometry;
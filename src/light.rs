@@ -1,14 +1,113 @@
 pub mod point_source;
+pub mod directional;
+pub mod spot;
+pub mod ies;
+pub mod area;
+pub mod environment;
+pub mod sky;
+pub mod sampler;
+pub mod linking;
+pub mod units;
 
 use crate::{
     image::Color,
-    vec3::Vec3, 
-    geometry::Hit, 
+    vec3::Vec3,
+    geometry::Hit,
+    object::Object,
     ray::Ray,
 };
 
+/// Light reaching a shaded point from one position on a light.
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Unit direction from the shaded point towards the light.
+    pub direction: Vec3,
+    /// Distance to the sampled position; infinite for lights at infinity.
+    pub distance: f64,
+    /// Light arriving along `direction`: radiance for area lights, or the
+    /// intensity already falling off with distance for point-like lights.
+    pub radiance: Color,
+    /// Solid angle density of `direction`; 1 for lights that can only be sampled one way.
+    pub pdf: f64,
+}
+
+/// Where a ray meets a light with extent, such as an area light or a light at infinity.
+#[derive(Debug, Clone, Copy)]
+pub struct LightHit {
+    /// Distance along the ray; infinite for lights at infinity.
+    pub distance: f64,
+    /// Surface normal where the ray meets the light; zero for lights at infinity.
+    pub normal: Vec3,
+    pub radiance: Color,
+}
+
+/// Ray leaving a light, starting a path traced from the light.
+#[derive(Debug, Clone, Copy)]
+pub struct EmissionSample {
+    pub point: Vec3,
+    /// Surface normal at `point`; zero for point lights, which have no surface.
+    pub normal: Vec3,
+    /// Unit direction the light leaves along.
+    pub direction: Vec3,
+    /// Light leaving along `direction`: radiance for area lights, intensity for point lights.
+    pub radiance: Color,
+    /// Density of `point` per unit area; 1 for point lights.
+    pub pdf_position: f64,
+    /// Solid angle density of `direction`.
+    pub pdf_direction: f64,
+}
+
 pub trait Light {
-    fn sample(&self) -> Vec3;
+    fn sample(&self, hit: &Hit) -> LightSample;
     fn color(&self) -> Color;
-    fn intensity(&self, ray: &Ray, hit: &Hit) -> f64;
-}
\ No newline at end of file
+    /// Total luminous power emitted, used to choose among many lights. Lights
+    /// at infinity, and those returning `None`, are shaded at every hit.
+    fn power(&self) -> Option<f64> {
+        None
+    }
+    /// Whether the light is at infinity, like the sun or the sky, so it cannot
+    /// start paths and is only ever sampled from the surfaces it lights.
+    fn is_infinite(&self) -> bool {
+        false
+    }
+    /// Whether the light shades `object`; see `linking::LinkedLight`.
+    fn illuminates(&self, _object: &Object) -> bool {
+        true
+    }
+    /// Whether the light is a single point or direction, which rays scattered
+    /// off surfaces can never hit, so only `sample` can find it.
+    fn is_delta(&self) -> bool {
+        true
+    }
+    /// Solid angle density with which `sample` picks `direction` from `hit`;
+    /// 0 for delta lights.
+    fn pdf(&self, _hit: &Hit, _direction: &Vec3) -> f64 {
+        0.0
+    }
+    /// Light seen along `ray` if it meets the light's emitting surface.
+    fn hit(&self, _ray: &Ray) -> Option<LightHit> {
+        None
+    }
+    /// Whether `point` is within the light's range. Lights with a cutoff
+    /// radius are culled beyond it without tracing a shadow ray.
+    fn reaches(&self, _point: &Vec3) -> bool {
+        true
+    }
+    /// Ray leaving the light, for tracing paths from it. `None` for lights
+    /// at infinity, which cannot start paths.
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        None
+    }
+    /// Densities with which `sample_emission` picks `point` and `direction`.
+    fn pdf_emission(&self, _point: &Vec3, _direction: &Vec3) -> (f64, f64) {
+        (0.0, 0.0)
+    }
+    /// Light leaving `point` on the light along `direction`, as in `EmissionSample::radiance`.
+    fn emission(&self, _point: &Vec3, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    /// Radiance seen along `direction` by rays that miss every object.
+    fn background(&self, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+}
@@ -0,0 +1,467 @@
+use std::f64::consts::PI;
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, LightHit, EmissionSample, units},
+    ray::Ray,
+    geometry::{Geometry, sphere::Sphere},
+    geometry::Hit,
+    frame::Frame,
+    sampling,
+};
+
+/// Sample of a light from a point chosen uniformly over its area: converts the
+/// area density into a solid angle density as seen from `p`.
+fn area_sample(p: &Vec3, point: &Vec3, normal: &Vec3, area: f64, radiance: Color, two_sided: bool) -> LightSample {
+    let to_light = *point - *p;
+    let distance = to_light.length();
+    let direction = to_light.scale(1.0 / distance);
+    let mut cos_light = -direction.dot(normal);
+    if two_sided {
+        cos_light = cos_light.abs();
+    }
+    if cos_light <= 0.0 {
+        return LightSample {
+            direction,
+            distance,
+            radiance: Color::new(0.0, 0.0, 0.0),
+            pdf: 1.0,
+        };
+    }
+    LightSample {
+        direction,
+        distance,
+        radiance,
+        pdf: distance * distance / (cos_light * area),
+    }
+}
+
+/// Ray leaving `point` on a surface of `area` facing `normal`, in a
+/// cosine-distributed direction.
+fn cosine_emission(point: Vec3, normal: Vec3, area: f64, radiance: Color) -> EmissionSample {
+    let local = sampling::cosine_hemisphere(rand::random(), rand::random());
+    EmissionSample {
+        point,
+        normal,
+        direction: Frame::from_normal(&normal).to_world(&local),
+        radiance,
+        pdf_position: 1.0 / area,
+        pdf_direction: sampling::cosine_hemisphere_pdf(local.z),
+    }
+}
+
+/// Densities with which `cosine_emission` leaves a surface facing `normal` along `direction`.
+fn cosine_emission_pdf(normal: &Vec3, area: f64, direction: &Vec3) -> (f64, f64) {
+    let cos = direction.normalize().dot(normal);
+    (1.0 / area, if cos > 0.0 { sampling::cosine_hemisphere_pdf(cos) } else { 0.0 })
+}
+
+/// Radiance leaving a surface facing `normal` along `direction`, from its front only.
+fn front_emission(normal: &Vec3, direction: &Vec3, radiance: Color) -> Color {
+    if direction.dot(normal) > 0.0 { radiance } else { Color::new(0.0, 0.0, 0.0) }
+}
+
+/// Distance and position where `ray` crosses the front of the plane through
+/// `point` facing `normal`.
+fn front_hit(ray: &Ray, point: &Vec3, normal: &Vec3) -> Option<(f64, Vec3)> {
+    let direction = ray.direction.normalize();
+    let denom = direction.dot(normal);
+    if denom >= 0.0 {
+        return None;
+    }
+    let distance = (*point - ray.origin).dot(normal) / denom;
+    if distance <= 0.0 {
+        return None;
+    }
+    Some((distance, ray.origin + direction * distance))
+}
+
+/// Solid angle density of reaching a one-sided light along `direction`,
+/// having sampled it uniformly by area.
+fn front_pdf(light: &dyn Light, hit: &Hit, direction: &Vec3, normal: &Vec3, area: f64) -> f64 {
+    match light.hit(&Ray::new(hit.point, *direction)) {
+        Some(light_hit) => light_hit.distance * light_hit.distance / (-direction.dot(normal) * area),
+        None => 0.0,
+    }
+}
+
+/// Spherical light of uniform `radiance`, sampled over the cone it subtends.
+pub struct SphereLight {
+    pub center: Vec3,
+    pub radius: f64,
+    pub color: Color,
+    pub radiance: f64,
+}
+
+impl SphereLight {
+    pub fn new(center: Vec3, radius: f64, color: Color, radiance: f64) -> SphereLight {
+        SphereLight {
+            center,
+            radius,
+            color,
+            radiance,
+        }
+    }
+    pub fn from_watts(center: Vec3, radius: f64, color: Color, watts: f64) -> SphereLight {
+        let radiance = units::radiance_from_watts(watts, 4.0 * PI * radius * radius);
+        SphereLight::new(center, radius, color, radiance * units::luminance_scale(&color))
+    }
+    pub fn from_lumens(center: Vec3, radius: f64, color: Color, lumens: f64) -> SphereLight {
+        SphereLight::from_watts(center, radius, color, units::watts_from_lumens(lumens))
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let to_center = self.center - hit.point;
+        let distance_squared = to_center.dot(&to_center);
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            let normal = sampling::uniform_sphere(rand::random(), rand::random());
+            let point = self.center + normal * self.radius;
+            return area_sample(&hit.point, &point, &normal, 4.0 * PI * radius_squared, self.color * self.radiance, true);
+        }
+        let cos_max = (1.0 - radius_squared / distance_squared).max(0.0).sqrt();
+        let local = sampling::uniform_cone(rand::random(), rand::random(), cos_max);
+        let direction = Frame::from_normal(&to_center.scale(1.0 / distance_squared.sqrt())).to_world(&local);
+        let b = direction.dot(&to_center);
+        let distance = b - (b * b - distance_squared + radius_squared).max(0.0).sqrt();
+        LightSample {
+            direction,
+            distance,
+            radiance: self.color * self.radiance,
+            pdf: sampling::uniform_cone_pdf(cos_max),
+        }
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn pdf(&self, hit: &Hit, direction: &Vec3) -> f64 {
+        let to_center = self.center - hit.point;
+        let distance_squared = to_center.dot(&to_center);
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            let sphere_hit = match Sphere::new(self.center, self.radius).hit(&Ray::new(hit.point, *direction)) {
+                Some(sphere_hit) => sphere_hit,
+                None => return 0.0,
+            };
+            let cos_light = direction.dot(&sphere_hit.normal).abs();
+            return sphere_hit.distance * sphere_hit.distance / (cos_light * 4.0 * PI * radius_squared);
+        }
+        let cos_max = (1.0 - radius_squared / distance_squared).max(0.0).sqrt();
+        if direction.dot(&to_center) / distance_squared.sqrt() < cos_max {
+            return 0.0;
+        }
+        sampling::uniform_cone_pdf(cos_max)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        let sphere_hit = Sphere::new(self.center, self.radius).hit(ray)?;
+        Some(LightHit {
+            distance: sphere_hit.distance,
+            normal: sphere_hit.normal,
+            radiance: self.color * self.radiance,
+        })
+    }
+
+    fn power(&self) -> Option<f64> {
+        let area = 4.0 * PI * self.radius * self.radius;
+        Some(PI * area * self.radiance * self.color.luminance())
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let normal = sampling::uniform_sphere(rand::random(), rand::random());
+        let area = 4.0 * PI * self.radius * self.radius;
+        Some(cosine_emission(self.center + normal * self.radius, normal, area, self.color * self.radiance))
+    }
+
+    fn pdf_emission(&self, point: &Vec3, direction: &Vec3) -> (f64, f64) {
+        let normal = (*point - self.center).normalize();
+        cosine_emission_pdf(&normal, 4.0 * PI * self.radius * self.radius, direction)
+    }
+
+    fn emission(&self, point: &Vec3, direction: &Vec3) -> Color {
+        front_emission(&(*point - self.center), direction, self.color * self.radiance)
+    }
+}
+
+/// Parallelogram light spanned by `edge_u` and `edge_v` from `corner`,
+/// emitting from the side `edge_u × edge_v` points to.
+pub struct RectLight {
+    pub corner: Vec3,
+    pub edge_u: Vec3,
+    pub edge_v: Vec3,
+    pub color: Color,
+    pub radiance: f64,
+}
+
+impl RectLight {
+    pub fn new(corner: Vec3, edge_u: Vec3, edge_v: Vec3, color: Color, radiance: f64) -> RectLight {
+        RectLight {
+            corner,
+            edge_u,
+            edge_v,
+            color,
+            radiance,
+        }
+    }
+    pub fn from_watts(corner: Vec3, edge_u: Vec3, edge_v: Vec3, color: Color, watts: f64) -> RectLight {
+        let radiance = units::radiance_from_watts(watts, edge_u.cross(&edge_v).length());
+        RectLight::new(corner, edge_u, edge_v, color, radiance * units::luminance_scale(&color))
+    }
+    pub fn from_lumens(corner: Vec3, edge_u: Vec3, edge_v: Vec3, color: Color, lumens: f64) -> RectLight {
+        RectLight::from_watts(corner, edge_u, edge_v, color, units::watts_from_lumens(lumens))
+    }
+}
+
+impl Light for RectLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let cross = self.edge_u.cross(&self.edge_v);
+        let area = cross.length();
+        let point = self.corner + self.edge_u * rand::random::<f64>() + self.edge_v * rand::random::<f64>();
+        area_sample(&hit.point, &point, &cross.scale(1.0 / area), area, self.color * self.radiance, false)
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn pdf(&self, hit: &Hit, direction: &Vec3) -> f64 {
+        let cross = self.edge_u.cross(&self.edge_v);
+        let area = cross.length();
+        front_pdf(self, hit, direction, &cross.scale(1.0 / area), area)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        let cross = self.edge_u.cross(&self.edge_v);
+        let area = cross.length();
+        let normal = cross.scale(1.0 / area);
+        let (distance, point) = front_hit(ray, &self.corner, &normal)?;
+        let offset = point - self.corner;
+        let u = offset.cross(&self.edge_v).dot(&normal) / area;
+        let v = self.edge_u.cross(&offset).dot(&normal) / area;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+        Some(LightHit {
+            distance,
+            normal,
+            radiance: self.color * self.radiance,
+        })
+    }
+
+    fn power(&self) -> Option<f64> {
+        let area = self.edge_u.cross(&self.edge_v).length();
+        Some(PI * area * self.radiance * self.color.luminance())
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let cross = self.edge_u.cross(&self.edge_v);
+        let area = cross.length();
+        let point = self.corner + self.edge_u * rand::random::<f64>() + self.edge_v * rand::random::<f64>();
+        Some(cosine_emission(point, cross.scale(1.0 / area), area, self.color * self.radiance))
+    }
+
+    fn pdf_emission(&self, _point: &Vec3, direction: &Vec3) -> (f64, f64) {
+        let cross = self.edge_u.cross(&self.edge_v);
+        let area = cross.length();
+        cosine_emission_pdf(&cross.scale(1.0 / area), area, direction)
+    }
+
+    fn emission(&self, _point: &Vec3, direction: &Vec3) -> Color {
+        front_emission(&self.edge_u.cross(&self.edge_v), direction, self.color * self.radiance)
+    }
+}
+
+/// Disk light emitting from the side `normal` points to.
+pub struct DiskLight {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub color: Color,
+    pub radiance: f64,
+}
+
+impl DiskLight {
+    pub fn new(center: Vec3, normal: Vec3, radius: f64, color: Color, radiance: f64) -> DiskLight {
+        DiskLight {
+            center,
+            normal: normal.normalize(),
+            radius,
+            color,
+            radiance,
+        }
+    }
+    pub fn from_watts(center: Vec3, normal: Vec3, radius: f64, color: Color, watts: f64) -> DiskLight {
+        let radiance = units::radiance_from_watts(watts, PI * radius * radius);
+        DiskLight::new(center, normal, radius, color, radiance * units::luminance_scale(&color))
+    }
+    pub fn from_lumens(center: Vec3, normal: Vec3, radius: f64, color: Color, lumens: f64) -> DiskLight {
+        DiskLight::from_watts(center, normal, radius, color, units::watts_from_lumens(lumens))
+    }
+}
+
+impl Light for DiskLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let frame = Frame::from_normal(&self.normal);
+        let (x, y) = sampling::concentric_disk(rand::random(), rand::random());
+        let point = self.center + frame.s * (x * self.radius) + frame.t * (y * self.radius);
+        area_sample(&hit.point, &point, &self.normal, PI * self.radius * self.radius, self.color * self.radiance, false)
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn pdf(&self, hit: &Hit, direction: &Vec3) -> f64 {
+        front_pdf(self, hit, direction, &self.normal, PI * self.radius * self.radius)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        let (distance, point) = front_hit(ray, &self.center, &self.normal)?;
+        let offset = point - self.center;
+        if offset.dot(&offset) > self.radius * self.radius {
+            return None;
+        }
+        Some(LightHit {
+            distance,
+            normal: self.normal,
+            radiance: self.color * self.radiance,
+        })
+    }
+
+    fn power(&self) -> Option<f64> {
+        let area = PI * self.radius * self.radius;
+        Some(PI * area * self.radiance * self.color.luminance())
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let frame = Frame::from_normal(&self.normal);
+        let (x, y) = sampling::concentric_disk(rand::random(), rand::random());
+        let point = self.center + frame.s * (x * self.radius) + frame.t * (y * self.radius);
+        Some(cosine_emission(point, self.normal, PI * self.radius * self.radius, self.color * self.radiance))
+    }
+
+    fn pdf_emission(&self, _point: &Vec3, direction: &Vec3) -> (f64, f64) {
+        cosine_emission_pdf(&self.normal, PI * self.radius * self.radius, direction)
+    }
+
+    fn emission(&self, _point: &Vec3, direction: &Vec3) -> Color {
+        front_emission(&self.normal, direction, self.color * self.radiance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Monte Carlo estimate of the irradiance from `light` at the origin, facing +z.
+    fn irradiance(light: &dyn Light) -> f64 {
+        let samples = 100_000;
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            let sample = light.sample(&Hit::at_origin());
+            sum += sample.radiance.red * sample.direction.z.max(0.0) / sample.pdf;
+        }
+        sum / samples as f64
+    }
+
+    #[test]
+    fn test_sphere_light_irradiance() {
+        // A sphere of radiance L directly overhead subtending sin² of its
+        // half-angle delivers E = π L sin².
+        let light = SphereLight::new(Vec3::new(0.0, 0.0, 4.0), 1.0, Color::new(1.0, 1.0, 1.0), 2.0);
+        assert_approx_eq!(irradiance(&light), PI * 2.0 / 16.0, 1e-3);
+    }
+
+    #[test]
+    fn test_disk_and_rect_light_irradiance() {
+        // A facing disk of radius r at height h delivers E = π L r² / (h² + r²).
+        let disk = DiskLight::new(Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -1.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0);
+        assert_approx_eq!(irradiance(&disk), PI / 5.0, 1e-2);
+        let rect = RectLight::new(
+            Vec3::new(-1.0, -1.0, 2.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            1.0,
+        );
+        assert!(irradiance(&rect) > PI / 5.0);
+        let backwards = RectLight::new(
+            Vec3::new(-1.0, -1.0, 2.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            1.0,
+        );
+        assert_approx_eq!(irradiance(&backwards), 0.0);
+    }
+
+    #[test]
+    fn test_pdf_and_hit_match_sampling() {
+        let lights: Vec<Box<dyn Light>> = vec![
+            Box::new(SphereLight::new(Vec3::new(0.0, 0.5, 3.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0)),
+            Box::new(SphereLight::new(Vec3::new(0.0, 0.0, 0.5), 1.0, Color::new(1.0, 1.0, 1.0), 1.0)),
+            Box::new(RectLight::new(
+                Vec3::new(-1.0, -1.0, 2.0),
+                Vec3::new(0.0, 2.0, 0.0),
+                Vec3::new(1.5, 0.5, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+                1.0,
+            )),
+            Box::new(DiskLight::new(Vec3::new(0.5, 0.0, 2.0), Vec3::new(0.0, -0.3, -1.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0)),
+        ];
+        for light in &lights {
+            for _ in 0..1000 {
+                let sample = light.sample(&Hit::at_origin());
+                if sample.radiance.is_black() {
+                    continue;
+                }
+                let pdf = light.pdf(&Hit::at_origin(), &sample.direction);
+                assert!((pdf / sample.pdf - 1.0).abs() < 1e-6);
+                let light_hit = light.hit(&Ray::new(Hit::at_origin().point, sample.direction)).unwrap();
+                assert_approx_eq!(light_hit.distance, sample.distance, 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_emission_pdf_matches_sampling() {
+        let lights: Vec<Box<dyn Light>> = vec![
+            Box::new(SphereLight::new(Vec3::new(0.0, 0.5, 3.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0)),
+            Box::new(RectLight::new(
+                Vec3::new(-1.0, -1.0, 2.0),
+                Vec3::new(0.0, 2.0, 0.0),
+                Vec3::new(1.5, 0.5, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+                1.0,
+            )),
+            Box::new(DiskLight::new(Vec3::new(0.5, 0.0, 2.0), Vec3::new(0.0, -0.3, -1.0), 1.0, Color::new(1.0, 1.0, 1.0), 1.0)),
+        ];
+        for light in &lights {
+            for _ in 0..100 {
+                let sample = light.sample_emission().unwrap();
+                let (pdf_position, pdf_direction) = light.pdf_emission(&sample.point, &sample.direction);
+                assert_approx_eq!(pdf_position, sample.pdf_position, 1e-9);
+                assert_approx_eq!(pdf_direction, sample.pdf_direction, 1e-9);
+                assert_approx_eq!(light.emission(&sample.point, &sample.direction).red, sample.radiance.red);
+                assert!(light.emission(&sample.point, &-sample.direction).is_black());
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, units},
+    geometry::Hit,
+    frame::Frame,
+    sampling,
+};
+
+/// Distant light such as the sun. `direction` is the direction the light
+/// travels in and `irradiance` what it delivers to a surface facing it.
+/// A nonzero `angular_diameter`, in radians, spreads it over a disk in the sky
+/// for soft shadows; the sun's is about 0.0093.
+pub struct DirectionalLight {
+    pub direction: Vec3,
+    pub color: Color,
+    pub irradiance: f64,
+    pub angular_diameter: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, color: Color, irradiance: f64) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalize(),
+            color,
+            irradiance,
+            angular_diameter: 0.0,
+        }
+    }
+    /// Light delivering an illuminance of `lux` to a surface facing it;
+    /// direct sunlight is around 100 000 lux.
+    pub fn from_lux(direction: Vec3, color: Color, lux: f64) -> DirectionalLight {
+        DirectionalLight::new(direction, color, units::watts_per_m2_from_lux(lux) * units::luminance_scale(&color))
+    }
+    pub fn sun(direction: Vec3, color: Color, irradiance: f64) -> DirectionalLight {
+        DirectionalLight {
+            angular_diameter: 0.0093,
+            ..DirectionalLight::new(direction, color, irradiance)
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _hit: &Hit) -> LightSample {
+        let to_light = -self.direction;
+        let direction = if self.angular_diameter > 0.0 {
+            let cos_max = (self.angular_diameter / 2.0).cos();
+            let local = sampling::uniform_cone(rand::random(), rand::random(), cos_max);
+            Frame::from_normal(&to_light).to_world(&local)
+        } else {
+            to_light
+        };
+        LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.color * self.irradiance,
+            pdf: 1.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
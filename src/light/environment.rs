@@ -0,0 +1,147 @@
+use std::{
+    f64::consts::PI,
+    path::Path,
+};
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, LightHit},
+    ray::Ray,
+    geometry::Hit,
+    texture::image_texture::ImageTexture,
+    sampling::Distribution2d,
+};
+
+/// Image-based light from an equirectangular map (Radiance `.hdr` or OpenEXR)
+/// surrounding the scene, with +y up. Seen directly as the background and
+/// sampled in proportion to its luminance. `rotation` turns the map about the
+/// y axis, in radians.
+pub struct EnvironmentLight {
+    pub map: ImageTexture,
+    pub intensity: f64,
+    pub rotation: f64,
+    distribution: Distribution2d,
+}
+
+impl EnvironmentLight {
+    pub fn new(map: ImageTexture, intensity: f64, rotation: f64) -> EnvironmentLight {
+        let (width, height) = (map.width as usize, map.height as usize);
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            for x in 0..width {
+                weights.push(map.texel(x as i64, y as i64).luminance() * sin_theta);
+            }
+        }
+        EnvironmentLight {
+            distribution: Distribution2d::new(&weights, width, height),
+            map,
+            intensity,
+            rotation,
+        }
+    }
+    pub fn open<P: AsRef<Path>>(path: P, intensity: f64, rotation: f64) -> Result<EnvironmentLight, ::image::ImageError> {
+        Ok(EnvironmentLight::new(ImageTexture::open(path, false)?, intensity, rotation))
+    }
+    fn to_uv(&self, direction: &Vec3) -> (f64, f64) {
+        let phi = (direction.z.atan2(direction.x) - self.rotation).rem_euclid(2.0 * PI);
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        (phi / (2.0 * PI), theta / PI)
+    }
+    fn to_direction(&self, u: f64, v: f64) -> Vec3 {
+        let phi = u * 2.0 * PI + self.rotation;
+        let theta = v * PI;
+        Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+    }
+    fn lookup(&self, u: f64, v: f64) -> Color {
+        let x = ((u * self.map.width as f64) as i64).min(self.map.width as i64 - 1);
+        let y = ((v * self.map.height as f64) as i64).min(self.map.height as i64 - 1);
+        self.map.texel(x, y) * self.intensity
+    }
+    /// Solid angle density of sampling `direction`.
+    pub fn pdf(&self, direction: &Vec3) -> f64 {
+        let (u, v) = self.to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _hit: &Hit) -> LightSample {
+        let ((u, v), _) = self.distribution.sample(rand::random(), rand::random());
+        // Radiance and density come from the direction itself, so that a sample
+        // on a texel edge maps to the same texel as `background` and `pdf`.
+        let direction = self.to_direction(u, v);
+        let pdf = EnvironmentLight::pdf(self, &direction);
+        if pdf <= 0.0 {
+            return LightSample {
+                direction: Vec3::new(0.0, 1.0, 0.0),
+                distance: f64::INFINITY,
+                radiance: Color::new(0.0, 0.0, 0.0),
+                pdf: 1.0,
+            };
+        }
+        LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.background(&direction),
+            pdf,
+        }
+    }
+
+    fn color(&self) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn background(&self, direction: &Vec3) -> Color {
+        let (u, v) = self.to_uv(&direction.normalize());
+        self.lookup(u, v)
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+
+    fn pdf(&self, _hit: &Hit, direction: &Vec3) -> f64 {
+        EnvironmentLight::pdf(self, direction)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        Some(LightHit {
+            distance: f64::INFINITY,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.background(&ray.direction),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_sampled_radiance_matches_background() {
+        let texels = (0..32).map(|i| Color::new(i as f64, 1.0, 0.5)).collect();
+        let light = EnvironmentLight::new(ImageTexture::new(8, 4, texels), 2.0, 0.7);
+        let hit = Hit::at_origin();
+        let samples = 20_000;
+        let mut integral = 0.0;
+        for _ in 0..samples {
+            let sample = light.sample(&hit);
+            let background = light.background(&sample.direction);
+            assert_approx_eq!(sample.radiance.red, background.red, 1e-6);
+            assert_approx_eq!(sample.pdf, light.pdf(&sample.direction), 1e-6);
+            integral += sample.radiance.green / sample.pdf;
+        }
+        // The green channel is constant, so its integral over the sphere is 4π times 2.
+        assert_approx_eq!(integral / samples as f64, 8.0 * PI, 0.5);
+    }
+}
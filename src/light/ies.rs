@@ -0,0 +1,319 @@
+use std::{
+    f64::consts::PI,
+    fmt,
+    fs,
+    path::Path,
+};
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, EmissionSample, units},
+    geometry::Hit,
+    frame::Frame,
+    sampling,
+};
+
+/// Slack in degrees when testing whether an angle lies within the table, so
+/// rounding in the direction to angle conversion does not cut off its edges.
+const ANGLE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug)]
+pub enum IesError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for IesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IesError::Io(error) => write!(f, "could not read IES file: {}", error),
+            IesError::Parse(message) => write!(f, "invalid IES file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for IesError {}
+
+impl From<std::io::Error> for IesError {
+    fn from(error: std::io::Error) -> IesError {
+        IesError::Io(error)
+    }
+}
+
+/// Candela distribution of a luminaire from an IESNA LM-63 file, in type C
+/// photometry: vertical angles from the nadir, horizontal angles around it.
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    /// Vertical angles in degrees, ascending.
+    pub vertical_angles: Vec<f64>,
+    /// Horizontal angles in degrees, ascending.
+    pub horizontal_angles: Vec<f64>,
+    /// Candela values, one row of vertical samples per horizontal angle,
+    /// with the file's multiplier applied.
+    pub candela: Vec<Vec<f64>>,
+}
+
+impl IesProfile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IesProfile, IesError> {
+        IesProfile::parse(&fs::read_to_string(path)?)
+    }
+    pub fn parse(source: &str) -> Result<IesProfile, IesError> {
+        let mut lines = source.lines();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if let Some(tilt) = line.strip_prefix("TILT=") {
+                if tilt.trim() != "NONE" {
+                    return Err(IesError::Parse(format!("unsupported tilt {}", tilt.trim())));
+                }
+                break;
+            }
+        }
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f64>().map_err(|_| IesError::Parse(format!("bad number {}", token))));
+        let mut next = || numbers.next().unwrap_or_else(|| Err(IesError::Parse("unexpected end of file".to_string())));
+        let _lamps = next()?;
+        let _lumens = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()?;
+        if photometric_type != 1.0 {
+            return Err(IesError::Parse(format!("unsupported photometric type {}", photometric_type)));
+        }
+        for _ in 0..7 {
+            next()?;
+        }
+        let vertical_angles = (0..vertical_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..horizontal_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let candela = (0..horizontal_count)
+            .map(|_| (0..vertical_count).map(|_| next().map(|c| c * multiplier)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(IesError::Parse("empty candela table".to_string()));
+        }
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+    /// Maps a horizontal angle into the range covered by the table, using the
+    /// symmetry implied by the last horizontal angle.
+    fn fold_horizontal(&self, phi: f64) -> f64 {
+        let last = *self.horizontal_angles.last().unwrap();
+        let phi = phi.rem_euclid(360.0);
+        if last <= 0.0 {
+            0.0
+        } else if last <= 90.0 {
+            let phi = phi % 180.0;
+            if phi > 90.0 { 180.0 - phi } else { phi }
+        } else if last <= 180.0 {
+            if phi > 180.0 { 360.0 - phi } else { phi }
+        } else {
+            phi
+        }
+    }
+    /// Piecewise-linear lookup of `x` in ascending `angles`, or `None`
+    /// outside the range they cover.
+    fn bracket(angles: &[f64], x: f64) -> Option<(usize, usize, f64)> {
+        let last = angles.len() - 1;
+        if x < angles[0] - ANGLE_TOLERANCE || x > angles[last] + ANGLE_TOLERANCE {
+            return None;
+        }
+        if x <= angles[0] {
+            return Some((0, 0, 0.0));
+        }
+        if x >= angles[last] {
+            return Some((last, last, 0.0));
+        }
+        let i = angles.partition_point(|&a| a <= x) - 1;
+        let t = (x - angles[i]) / (angles[i + 1] - angles[i]);
+        Some((i, i + 1, t))
+    }
+    /// Luminous intensity in candela at vertical angle `theta` and horizontal
+    /// angle `phi`, both in degrees; zero outside the angles the table covers.
+    pub fn candela(&self, theta: f64, phi: f64) -> f64 {
+        let Some((v0, v1, tv)) = IesProfile::bracket(&self.vertical_angles, theta) else {
+            return 0.0;
+        };
+        let Some((h0, h1, th)) = IesProfile::bracket(&self.horizontal_angles, self.fold_horizontal(phi)) else {
+            return 0.0;
+        };
+        let row = |h: usize| self.candela[h][v0] * (1.0 - tv) + self.candela[h][v1] * tv;
+        row(h0) * (1.0 - th) + row(h1) * th
+    }
+}
+
+/// Point light whose intensity follows a measured IES distribution. The
+/// profile's nadir points along `direction` and its zero horizontal angle along
+/// the first tangent of the frame around it. `scale` converts candela into
+/// scene intensity units.
+pub struct IesLight {
+    pub position: Vec3,
+    pub frame: Frame,
+    pub color: Color,
+    pub scale: f64,
+    pub profile: IesProfile,
+}
+
+impl IesLight {
+    pub fn new(position: Vec3, direction: Vec3, color: Color, scale: f64, profile: IesProfile) -> IesLight {
+        IesLight {
+            position,
+            frame: Frame::from_normal(&direction.normalize()),
+            color,
+            scale,
+            profile,
+        }
+    }
+    /// Light emitting the profile's candela values as measured.
+    pub fn photometric(position: Vec3, direction: Vec3, color: Color, profile: IesProfile) -> IesLight {
+        let scale = units::watts_per_sr_from_candela(1.0) * units::luminance_scale(&color);
+        IesLight::new(position, direction, color, scale, profile)
+    }
+    /// Intensity emitted along `w`, pointing away from the light.
+    pub fn intensity(&self, w: &Vec3) -> f64 {
+        let local = self.frame.to_local(w);
+        let theta = local.z.clamp(-1.0, 1.0).acos().to_degrees();
+        let phi = local.y.atan2(local.x).to_degrees();
+        self.profile.candela(theta, phi) * self.scale
+    }
+}
+
+impl Light for IesLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let to_light = self.position - hit.point;
+        let distance = to_light.length();
+        let direction = to_light.scale(1.0 / distance);
+        LightSample {
+            direction,
+            distance,
+            radiance: self.color * (self.intensity(&-direction) / (distance * distance)),
+            pdf: 1.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn power(&self) -> Option<f64> {
+        // Midpoint rule over a latitude-longitude grid of the sphere.
+        let (rows, columns) = (32, 64);
+        let mut sum = 0.0;
+        for i in 0..rows {
+            let theta = PI * (i as f64 + 0.5) / rows as f64;
+            for j in 0..columns {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / columns as f64;
+                let local = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+                sum += self.intensity(&self.frame.to_world(&local)) * theta.sin();
+            }
+        }
+        let cell = PI / rows as f64 * 2.0 * PI / columns as f64;
+        Some(sum * cell * self.color.luminance())
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let direction = sampling::uniform_sphere(rand::random(), rand::random());
+        Some(EmissionSample {
+            point: self.position,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            direction,
+            radiance: self.color * self.intensity(&direction),
+            pdf_position: 1.0,
+            pdf_direction: sampling::uniform_sphere_pdf(),
+        })
+    }
+
+    fn pdf_emission(&self, _point: &Vec3, _direction: &Vec3) -> (f64, f64) {
+        (1.0, sampling::uniform_sphere_pdf())
+    }
+
+    fn emission(&self, _point: &Vec3, direction: &Vec3) -> Color {
+        self.color * self.intensity(&direction.normalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    const PROFILE: &str = "IESNA:LM-63-2002
+[TEST] test fixture
+[MANUFAC] nobody
+TILT=NONE
+1 1000 2.0 3 2 1 1 0.0 0.0 0.0
+1.0 1.0 100
+0 45 90
+0 90
+100 50 0
+80 40 0
+";
+
+    #[test]
+    fn test_parse_and_interpolate() {
+        let profile = IesProfile::parse(PROFILE).unwrap();
+        assert_eq!(profile.vertical_angles, vec![0.0, 45.0, 90.0]);
+        assert_eq!(profile.horizontal_angles, vec![0.0, 90.0]);
+        assert_approx_eq!(profile.candela(0.0, 0.0), 200.0);
+        assert_approx_eq!(profile.candela(22.5, 0.0), 150.0);
+        assert_approx_eq!(profile.candela(0.0, 45.0), 180.0);
+        // Quadrant symmetry folds 270 degrees onto 90.
+        assert_approx_eq!(profile.candela(45.0, 270.0), 80.0);
+        assert_approx_eq!(profile.candela(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_no_light_outside_the_table() {
+        let profile = IesProfile {
+            vertical_angles: vec![0.0, 45.0],
+            horizontal_angles: vec![0.0, 120.0],
+            candela: vec![vec![100.0, 100.0], vec![100.0, 100.0]],
+        };
+        assert_approx_eq!(profile.candela(45.0, 60.0), 100.0);
+        assert_approx_eq!(profile.candela(60.0, 60.0), 0.0);
+        // Bilateral symmetry maps 200 degrees onto 160, which the table lacks.
+        assert_approx_eq!(profile.candela(30.0, 200.0), 0.0);
+        assert_approx_eq!(profile.candela(30.0, 240.0), 100.0);
+    }
+
+    #[test]
+    fn test_light_follows_the_table() {
+        let profile = IesProfile::parse(PROFILE).unwrap();
+        let light = IesLight::photometric(Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -1.0), Color::new(1.0, 1.0, 1.0), profile);
+        let sample = light.sample(&Hit::at_origin());
+        assert_approx_eq!(sample.radiance.luminance(), units::watts_per_sr_from_candela(200.0) / 4.0);
+        let angle = 45.0_f64.to_radians();
+        let w = light.frame.to_world(&Vec3::new(angle.sin(), 0.0, angle.cos()));
+        let hit = Hit {
+            point: light.position + w * 2.0,
+            ..Hit::at_origin()
+        };
+        assert_approx_eq!(light.sample(&hit).radiance.luminance(), units::watts_per_sr_from_candela(100.0) / 4.0);
+        let above = Hit {
+            point: Vec3::new(0.0, 0.0, 4.0),
+            ..Hit::at_origin()
+        };
+        assert_approx_eq!(light.sample(&above).radiance.luminance(), 0.0);
+    }
+
+    #[test]
+    fn test_power_integrates_the_table() {
+        let profile = IesProfile {
+            vertical_angles: vec![0.0, 45.0],
+            horizontal_angles: vec![0.0],
+            candela: vec![vec![100.0, 100.0]],
+        };
+        let light = IesLight::photometric(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Color::new(1.0, 1.0, 1.0), profile);
+        let expected = 2.0 * PI * (1.0 - 45.0_f64.to_radians().cos()) * units::watts_per_sr_from_candela(100.0);
+        assert!((light.power().unwrap() - expected).abs() < 0.01 * expected);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_file() {
+        assert!(IesProfile::parse("TILT=NONE\n1 1000 1 3 2 1").is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, LightHit, EmissionSample},
+    ray::Ray,
+    geometry::Hit,
+    object::Object,
+};
+
+/// Objects a linked light is restricted to, matched by `Object::name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LightLinking {
+    /// Lights only the named objects.
+    Include(Vec<String>),
+    /// Lights everything except the named objects.
+    Exclude(Vec<String>),
+}
+
+impl LightLinking {
+    pub fn include(names: &[&str]) -> LightLinking {
+        LightLinking::Include(names.iter().map(|name| name.to_string()).collect())
+    }
+    pub fn exclude(names: &[&str]) -> LightLinking {
+        LightLinking::Exclude(names.iter().map(|name| name.to_string()).collect())
+    }
+    pub fn links(&self, object: &Object) -> bool {
+        match self {
+            LightLinking::Include(names) => names.contains(&object.name),
+            LightLinking::Exclude(names) => !names.contains(&object.name),
+        }
+    }
+}
+
+/// Wraps a light so it only shades the objects its `linking` selects, such as
+/// a rim light for a single hero object.
+pub struct LinkedLight {
+    pub light: Box<dyn Light>,
+    pub linking: LightLinking,
+}
+
+impl LinkedLight {
+    pub fn new(light: Box<dyn Light>, linking: LightLinking) -> LinkedLight {
+        LinkedLight {
+            light,
+            linking,
+        }
+    }
+}
+
+impl Light for LinkedLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        self.light.sample(hit)
+    }
+
+    fn color(&self) -> Color {
+        self.light.color()
+    }
+
+    fn background(&self, direction: &Vec3) -> Color {
+        self.light.background(direction)
+    }
+
+    fn power(&self) -> Option<f64> {
+        self.light.power()
+    }
+
+    fn is_delta(&self) -> bool {
+        self.light.is_delta()
+    }
+
+    fn is_infinite(&self) -> bool {
+        self.light.is_infinite()
+    }
+
+    fn pdf(&self, hit: &Hit, direction: &Vec3) -> f64 {
+        self.light.pdf(hit, direction)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        self.light.hit(ray)
+    }
+
+    fn reaches(&self, point: &Vec3) -> bool {
+        self.light.reaches(point)
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        self.light.sample_emission()
+    }
+
+    fn pdf_emission(&self, point: &Vec3, direction: &Vec3) -> (f64, f64) {
+        self.light.pdf_emission(point, direction)
+    }
+
+    fn emission(&self, point: &Vec3, direction: &Vec3) -> Color {
+        self.light.emission(point, direction)
+    }
+
+    fn illuminates(&self, object: &Object) -> bool {
+        self.linking.links(object) && self.light.illuminates(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::point_source::PointSource;
+
+    #[test]
+    fn test_linking() {
+        let light = LinkedLight::new(
+            Box::new(PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)),
+            LightLinking::include(&["hero"]),
+        );
+        assert!(light.illuminates(&Object::default().with_name("hero")));
+        assert!(!light.illuminates(&Object::default().with_name("floor")));
+        let excluding = LightLinking::exclude(&["floor"]);
+        assert!(excluding.links(&Object::default().with_name("hero")));
+        assert!(!excluding.links(&Object::default().with_name("floor")));
+    }
+}
@@ -1,14 +1,55 @@
+use std::f64::consts::PI;
 use crate::{
     image::Color,
     vec3::Vec3,
-    light::Light, geometry::Hit,
-    ray::Ray,
+    light::{Light, LightSample, EmissionSample, units},
+    geometry::Hit,
+    sampling,
 };
 
+/// Closest distance used by windowed attenuation, so it stays finite at the light.
+const WINDOWED_MIN_DISTANCE: f64 = 0.01;
+
+/// How a point light's intensity falls off with distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attenuation {
+    /// Physical falloff, with distances clamped to at least `min_radius` so
+    /// points next to the light do not blow up.
+    InverseSquare { min_radius: f64 },
+    /// Falls linearly from full intensity at the light to nothing at `radius`.
+    Linear { radius: f64 },
+    /// Constant intensity at any distance.
+    None,
+    /// Inverse-square falloff smoothly windowed to reach zero at `radius`.
+    Windowed { radius: f64 },
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f64) -> f64 {
+        match *self {
+            Attenuation::InverseSquare { min_radius } => 1.0 / distance.max(min_radius).powi(2),
+            Attenuation::Linear { radius } => (1.0 - distance / radius).max(0.0),
+            Attenuation::None => 1.0,
+            Attenuation::Windowed { radius } => {
+                let window = (1.0 - (distance / radius).powi(4)).clamp(0.0, 1.0);
+                window * window / distance.max(WINDOWED_MIN_DISTANCE).powi(2)
+            }
+        }
+    }
+    /// Distance past which the light has no effect, if any.
+    pub fn cutoff(&self) -> Option<f64> {
+        match *self {
+            Attenuation::Linear { radius } | Attenuation::Windowed { radius } => Some(radius),
+            Attenuation::InverseSquare { .. } | Attenuation::None => None,
+        }
+    }
+}
+
 pub struct PointSource {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f64,
+    pub attenuation: Attenuation,
 }
 
 impl PointSource {
@@ -17,21 +58,106 @@ impl PointSource {
             position,
             color,
             intensity,
+            attenuation: Attenuation::InverseSquare { min_radius: 0.0 },
         }
     }
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> PointSource {
+        self.attenuation = attenuation;
+        self
+    }
+    pub fn from_watts(position: Vec3, color: Color, watts: f64) -> PointSource {
+        PointSource::new(position, color, watts / (4.0 * PI) * units::luminance_scale(&color))
+    }
+    pub fn from_lumens(position: Vec3, color: Color, lumens: f64) -> PointSource {
+        PointSource::from_watts(position, color, units::watts_from_lumens(lumens))
+    }
+    pub fn from_candela(position: Vec3, color: Color, candela: f64) -> PointSource {
+        PointSource::new(position, color, units::watts_per_sr_from_candela(candela) * units::luminance_scale(&color))
+    }
 }
 
 impl Light for PointSource {
-    fn sample(&self) -> Vec3 {
-        self.position
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let to_light = self.position - hit.point;
+        let distance = to_light.length();
+        LightSample {
+            direction: to_light.scale(1.0 / distance),
+            distance,
+            radiance: self.color * (self.intensity * self.attenuation.factor(distance)),
+            pdf: 1.0,
+        }
     }
 
     fn color(&self) -> Color {
         self.color
     }
 
-    fn intensity(&self, _ray: &Ray, hit: &Hit) -> f64 {
-        let distance = (hit.point - self.position).length();
-        self.intensity / (distance * distance)
+    /// Power of the unattenuated light. Neither `attenuation` nor its cutoff
+    /// changes it, so light selection and photon emission treat every point
+    /// light as physical; only direct lighting applies the falloff.
+    fn power(&self) -> Option<f64> {
+        Some(4.0 * PI * self.intensity * self.color.luminance())
     }
-}
\ No newline at end of file
+
+    fn reaches(&self, point: &Vec3) -> bool {
+        match self.attenuation.cutoff() {
+            Some(radius) => (*point - self.position).length() < radius,
+            None => true,
+        }
+    }
+
+    /// Paths traced from the light fall off physically, whatever the `attenuation`.
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        Some(EmissionSample {
+            point: self.position,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            direction: sampling::uniform_sphere(rand::random(), rand::random()),
+            radiance: self.color * self.intensity,
+            pdf_position: 1.0,
+            pdf_direction: sampling::uniform_sphere_pdf(),
+        })
+    }
+
+    fn pdf_emission(&self, _point: &Vec3, _direction: &Vec3) -> (f64, f64) {
+        (1.0, sampling::uniform_sphere_pdf())
+    }
+
+    fn emission(&self, _point: &Vec3, _direction: &Vec3) -> Color {
+        self.color * self.intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_attenuation() {
+        let clamped = Attenuation::InverseSquare { min_radius: 0.5 };
+        assert_approx_eq!(clamped.factor(0.0), 4.0);
+        assert_approx_eq!(clamped.factor(2.0), 0.25);
+        assert_approx_eq!(Attenuation::Linear { radius: 4.0 }.factor(1.0), 0.75);
+        let windowed = Attenuation::Windowed { radius: 4.0 };
+        assert!(windowed.factor(1.0) < 1.0 && windowed.factor(1.0) > 0.9);
+        assert_approx_eq!(windowed.factor(4.0), 0.0);
+        let light = PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)
+            .with_attenuation(windowed);
+        assert!(light.reaches(&Vec3::new(0.0, 3.0, 0.0)));
+        assert!(!light.reaches(&Vec3::new(0.0, 5.0, 0.0)));
+    }
+
+    #[test]
+    fn test_power_and_emission_ignore_attenuation() {
+        let physical = PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 0.5, 0.25), 2.0);
+        let power = physical.power().unwrap();
+        for attenuation in [Attenuation::Linear { radius: 1.0 }, Attenuation::None, Attenuation::Windowed { radius: 0.5 }] {
+            let light = PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 0.5, 0.25), 2.0)
+                .with_attenuation(attenuation);
+            assert_approx_eq!(light.power().unwrap(), power);
+            let emission = light.sample_emission().unwrap();
+            assert_approx_eq!(emission.radiance.red, 2.0);
+            assert_approx_eq!(light.emission(&emission.point, &emission.direction).green, 1.0);
+        }
+    }
+}
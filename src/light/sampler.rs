@@ -0,0 +1,181 @@
+use std::f64::consts::PI;
+use crate::{
+    light::Light,
+    object::Object,
+    sampling::Distribution1d,
+};
+
+/// Surface samples averaged to estimate the power of an emissive object.
+const EMITTER_POWER_SAMPLES: u32 = 16;
+
+/// A light in the scene: one of `Scene::lights`, or an object with an emissive material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightIndex {
+    Light(usize),
+    Emitter(usize),
+}
+
+/// Chooses which lights to shade at a hit. With `samples` set to 0 every
+/// light is shaded; otherwise that many lights are drawn in proportion to
+/// their power, so the shadow rays per hit stay fixed however many lights
+/// the scene holds. Lights at infinity, and any whose power is unknown, are
+/// always shaded.
+#[derive(Debug, Clone)]
+pub struct LightSampler {
+    pub samples: u32,
+    always: Vec<LightIndex>,
+    finite: Vec<LightIndex>,
+    distribution: Option<Distribution1d>,
+}
+
+impl LightSampler {
+    pub fn new(lights: &[Box<dyn Light>], objects: &[Object], samples: u32) -> LightSampler {
+        let mut always = Vec::new();
+        let mut finite = Vec::new();
+        let mut powers = Vec::new();
+        for (i, light) in lights.iter().enumerate() {
+            match light.power() {
+                Some(power) if !light.is_infinite() => {
+                    finite.push(LightIndex::Light(i));
+                    powers.push(power);
+                }
+                _ => always.push(LightIndex::Light(i)),
+            }
+        }
+        for (i, object) in objects.iter().enumerate() {
+            if !object.material.is_emissive() {
+                continue;
+            }
+            if let Some(power) = emitter_power(object) {
+                finite.push(LightIndex::Emitter(i));
+                powers.push(power);
+            }
+        }
+        let distribution = if powers.is_empty() { None } else { Some(Distribution1d::new(powers)) };
+        LightSampler {
+            samples,
+            always,
+            finite,
+            distribution,
+        }
+    }
+    /// Probability of drawing `index` in one of the `samples` draws, or 1 for
+    /// lights that are always shaded.
+    pub fn probability(&self, index: LightIndex) -> f64 {
+        match (&self.distribution, self.finite.iter().position(|&i| i == index)) {
+            (Some(distribution), Some(position)) if self.samples > 0 =>
+                distribution.pdf(position) / self.finite.len() as f64,
+            _ => 1.0,
+        }
+    }
+    /// Expected number of times `index` is shaded at a hit, which scales its
+    /// sampling density when weighing it against BSDF sampling.
+    pub fn expected_samples(&self, index: LightIndex) -> f64 {
+        if self.always.contains(&index) {
+            return 1.0;
+        }
+        if !self.finite.contains(&index) {
+            return 0.0;
+        }
+        if self.samples > 0 {
+            self.samples as f64 * self.probability(index)
+        } else {
+            1.0
+        }
+    }
+    /// Draws a single light with power in proportion to it, along with the
+    /// probability of drawing it.
+    pub fn pick(&self) -> Option<(LightIndex, f64)> {
+        let distribution = self.distribution.as_ref()?;
+        let (_, pdf, position) = distribution.sample(rand::random());
+        let probability = pdf / self.finite.len() as f64;
+        if probability > 0.0 { Some((self.finite[position], probability)) } else { None }
+    }
+    /// Probability of `pick` drawing `index`.
+    pub fn pick_probability(&self, index: LightIndex) -> f64 {
+        match (&self.distribution, self.finite.iter().position(|&i| i == index)) {
+            (Some(distribution), Some(position)) => distribution.pdf(position) / self.finite.len() as f64,
+            _ => 0.0,
+        }
+    }
+    /// Lights to shade at one hit, each with the weight its contribution is scaled by.
+    pub fn select(&self) -> Vec<(LightIndex, f64)> {
+        let mut selected: Vec<(LightIndex, f64)> = self.always.iter().map(|&i| (i, 1.0)).collect();
+        match &self.distribution {
+            Some(distribution) if self.samples > 0 => {
+                let n = self.finite.len() as f64;
+                for _ in 0..self.samples {
+                    let (_, pdf, position) = distribution.sample(rand::random());
+                    let probability = pdf / n;
+                    if probability > 0.0 {
+                        selected.push((self.finite[position], 1.0 / (probability * self.samples as f64)));
+                    }
+                }
+            }
+            _ => selected.extend(self.finite.iter().map(|&i| (i, 1.0))),
+        }
+        selected
+    }
+}
+
+/// Power of an emissive object from its emitted radiance and area, assuming
+/// it emits diffusely. `None` for surfaces that cannot be sampled.
+fn emitter_power(object: &Object) -> Option<f64> {
+    let mut sum = 0.0;
+    for _ in 0..EMITTER_POWER_SAMPLES {
+        let sample = object.geometry.sample()?;
+        if sample.pdf <= 0.0 {
+            return None;
+        }
+        let radiance = object.material.emitted(&sample.hit.normal, &sample.hit);
+        sum += radiance.luminance() / sample.pdf;
+    }
+    Some(PI * sum / EMITTER_POWER_SAMPLES as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::Color,
+        vec3::Vec3,
+        light::point_source::PointSource,
+    };
+
+    #[test]
+    fn test_selection_weights_are_unbiased() {
+        let lights: Vec<Box<dyn Light>> = vec![
+            Box::new(PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)),
+            Box::new(PointSource::new(Vec3::new(1.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 9.0)),
+            Box::new(PointSource::new(Vec3::new(2.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 30.0)),
+        ];
+        let sampler = LightSampler::new(&lights, &[], 2);
+        // The dimmest light is picked in few rounds, with a large weight, so
+        // its mean needs many rounds to settle well inside the tolerance.
+        let rounds = 500_000;
+        let mut totals = [0.0; 3];
+        for _ in 0..rounds {
+            let selected = sampler.select();
+            assert_eq!(selected.len(), 2);
+            for (index, weight) in selected {
+                if let LightIndex::Light(i) = index {
+                    totals[i] += weight;
+                }
+            }
+        }
+        for total in totals {
+            assert!((total / rounds as f64 - 1.0).abs() < 0.05);
+        }
+        assert!((sampler.probability(LightIndex::Light(2)) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_all_lights_without_samples() {
+        let lights: Vec<Box<dyn Light>> = vec![
+            Box::new(PointSource::new(Vec3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0)),
+            Box::new(PointSource::new(Vec3::new(1.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 9.0)),
+        ];
+        let selected = LightSampler::new(&lights, &[], 0).select();
+        assert_eq!(selected, vec![(LightIndex::Light(0), 1.0), (LightIndex::Light(1), 1.0)]);
+    }
+}
@@ -0,0 +1,232 @@
+use std::f64::consts::PI;
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, LightHit, directional::DirectionalLight},
+    ray::Ray,
+    geometry::Hit,
+    frame::Frame,
+    sampling,
+};
+
+/// Day of the year, from 1 on January 1st, in the Gregorian calendar.
+pub fn day_of_year(year: i32, month: u32, day: u32) -> u32 {
+    const DAYS_BEFORE: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month = month.clamp(1, 12);
+    DAYS_BEFORE[month as usize - 1] + day + if leap && month > 2 { 1 } else { 0 }
+}
+
+/// Unit direction towards the sun, with +y up, north along -z and east along +x,
+/// using the NOAA approximation of the solar position. Latitude and longitude
+/// are in degrees, north and east positive; `utc_hours` is the time of day in UTC.
+pub fn sun_direction(latitude: f64, longitude: f64, day_of_year: u32, utc_hours: f64) -> Vec3 {
+    let gamma = 2.0 * PI / 365.0 * (day_of_year as f64 - 1.0 + (utc_hours - 12.0) / 24.0);
+    let equation_of_time = 229.18 * (0.000075 + 0.001868 * gamma.cos() - 0.032077 * gamma.sin()
+        - 0.014615 * (2.0 * gamma).cos() - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos() + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos() + 0.00148 * (3.0 * gamma).sin();
+    let solar_minutes = utc_hours * 60.0 + equation_of_time + 4.0 * longitude;
+    let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+    let latitude = latitude.to_radians();
+    let cos_zenith = (latitude.sin() * declination.sin()
+        + latitude.cos() * declination.cos() * hour_angle.cos()).clamp(-1.0, 1.0);
+    let sin_zenith = (1.0 - cos_zenith * cos_zenith).sqrt();
+    // Azimuth measured clockwise from north.
+    let azimuth = hour_angle.sin().atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos()) + PI;
+    Vec3::new(azimuth.sin() * sin_zenith, cos_zenith, -azimuth.cos() * sin_zenith)
+}
+
+/// Perez sky luminance distribution function.
+fn perez(coefficients: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Procedural clear sky after Preetham, Shirley and Smits (1999), with +y up.
+/// `turbidity` ranges from about 2 (very clear) to 10 (hazy). Radiance is the
+/// model's luminance in kcd/m² scaled by `intensity`. The sky below the
+/// horizon is black.
+pub struct SkyLight {
+    pub sun_direction: Vec3,
+    pub turbidity: f64,
+    pub intensity: f64,
+    coefficients: [[f64; 5]; 3],
+    zenith: [f64; 3],
+}
+
+impl SkyLight {
+    pub fn new(sun_direction: Vec3, turbidity: f64, intensity: f64) -> SkyLight {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity;
+        let coefficients = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+        let theta = sun_direction.y.clamp(0.0, 1.0).acos();
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (theta2, theta3) = (theta * theta, theta * theta * theta);
+        let x = t * t * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * theta)
+            + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * theta + 0.00394)
+            + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * theta + 0.25886);
+        let y = t * t * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * theta)
+            + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * theta + 0.00516)
+            + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * theta + 0.26688);
+        SkyLight {
+            sun_direction,
+            turbidity,
+            intensity,
+            coefficients,
+            zenith: [luminance.max(0.0), x, y],
+        }
+    }
+    /// Sky for the sun's position at a place and time; see `sun_direction`.
+    pub fn at(latitude: f64, longitude: f64, day_of_year: u32, utc_hours: f64, turbidity: f64, intensity: f64) -> SkyLight {
+        SkyLight::new(sun_direction(latitude, longitude, day_of_year, utc_hours), turbidity, intensity)
+    }
+    /// Color of direct sunlight after extinction by the same atmosphere,
+    /// normalized to unit luminance for a sun at the zenith, so it dims as the
+    /// sun sets.
+    pub fn sun_color(&self) -> Color {
+        let cos_theta = self.sun_direction.y;
+        if cos_theta <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        // Relative optical air mass (Kasten and Young).
+        let zenith = cos_theta.acos().to_degrees();
+        let air_mass = 1.0 / (cos_theta + 0.50572 * (96.07995 - zenith).powf(-1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |air_mass: f64| {
+            let channel = |wavelength: f64| {
+                let rayleigh = 0.008735 * wavelength.powf(-4.08);
+                let aerosol = beta * wavelength.powf(-1.3);
+                (-air_mass * (rayleigh + aerosol)).exp()
+            };
+            Color::new(channel(0.680), channel(0.550), channel(0.440))
+        };
+        let reference = transmittance(1.0).luminance();
+        transmittance(air_mass) * (1.0 / reference.max(1e-9))
+    }
+    /// Directional sun consistent with this sky, in direction and color.
+    pub fn sun(&self, irradiance: f64) -> DirectionalLight {
+        DirectionalLight::sun(-self.sun_direction, self.sun_color(), irradiance)
+    }
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        if direction.y <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let cos_theta = direction.y.max(0.01);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let theta_sun = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            self.zenith[i] * perez(&self.coefficients[i], cos_theta, gamma)
+                / perez(&self.coefficients[i], 1.0, theta_sun)
+        });
+        if y <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        Color::new(
+            (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+            (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+            (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+        ) * self.intensity
+    }
+}
+
+impl Light for SkyLight {
+    fn sample(&self, _hit: &Hit) -> LightSample {
+        let local = sampling::cosine_hemisphere(rand::random(), rand::random());
+        let direction = Frame::from_normal(&Vec3::new(0.0, 1.0, 0.0)).to_world(&local);
+        LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.radiance(&direction),
+            pdf: sampling::cosine_hemisphere_pdf(local.z),
+        }
+    }
+
+    fn color(&self) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn background(&self, direction: &Vec3) -> Color {
+        self.radiance(&direction.normalize())
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn is_infinite(&self) -> bool {
+        true
+    }
+
+    fn pdf(&self, _hit: &Hit, direction: &Vec3) -> f64 {
+        if direction.y <= 0.0 {
+            return 0.0;
+        }
+        sampling::cosine_hemisphere_pdf(direction.normalize().y)
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<LightHit> {
+        Some(LightHit {
+            distance: f64::INFINITY,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.background(&ray.direction),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_day_of_year() {
+        assert_eq!(day_of_year(2023, 1, 1), 1);
+        assert_eq!(day_of_year(2023, 12, 31), 365);
+        assert_eq!(day_of_year(2024, 3, 1), 61);
+    }
+
+    #[test]
+    fn test_sun_position() {
+        // Equinox, solar noon at Greenwich on the equator: sun overhead.
+        let overhead = sun_direction(0.0, 0.0, day_of_year(2023, 3, 20), 12.1);
+        assert!(overhead.y > 0.99);
+        // Winter solstice, noon at 45°N: sun due south at about 21.5° elevation.
+        let winter = sun_direction(45.0, 0.0, day_of_year(2023, 12, 21), 12.0);
+        assert_approx_eq!(winter.y.asin().to_degrees(), 21.5, 0.5);
+        assert!(winter.z > 0.0 && winter.x.abs() < 0.02);
+        // Mid-morning the sun is in the east.
+        assert!(sun_direction(45.0, 0.0, 172, 9.0).x > 0.0);
+    }
+
+    #[test]
+    fn test_sky_is_brightest_near_the_sun() {
+        let sky = SkyLight::new(Vec3::new(0.0, 0.5, -1.0), 3.0, 1.0);
+        let towards = sky.radiance(&Vec3::new(0.0, 0.6, -1.0).normalize());
+        let away = sky.radiance(&Vec3::new(0.0, 0.6, 1.0).normalize());
+        assert!(towards.luminance() > away.luminance());
+        assert!(away.blue > away.red);
+        let sun = sky.sun_color();
+        assert!(sun.red > sun.blue);
+    }
+
+    #[test]
+    fn test_sun_dims_towards_the_horizon() {
+        let luminance = |elevation: f64| {
+            let elevation = elevation.to_radians();
+            SkyLight::new(Vec3::new(0.0, elevation.sin(), -elevation.cos()), 3.0, 1.0).sun_color().luminance()
+        };
+        assert_approx_eq!(luminance(90.0), 1.0, 1e-3);
+        assert!(luminance(60.0) < luminance(90.0));
+        assert!(luminance(10.0) < luminance(60.0));
+        assert!(luminance(2.0) < 0.5 * luminance(10.0));
+    }
+}
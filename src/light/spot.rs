@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+use crate::{
+    image::Color,
+    vec3::Vec3,
+    light::{Light, LightSample, EmissionSample, units},
+    geometry::Hit,
+    frame::Frame,
+    sampling,
+};
+
+/// Point light restricted to a cone around `direction`. Full intensity inside
+/// `inner_angle`, fading smoothly to nothing at `outer_angle`; both are
+/// half-angles in radians.
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f64,
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+}
+
+impl SpotLight {
+    pub fn new(position: Vec3, direction: Vec3, color: Color, intensity: f64, inner_angle: f64, outer_angle: f64) -> SpotLight {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            color,
+            intensity,
+            inner_angle,
+            outer_angle,
+        }
+    }
+    /// Spot light emitting `lumens` in total, spread over its cone.
+    pub fn from_lumens(position: Vec3, direction: Vec3, color: Color, lumens: f64, inner_angle: f64, outer_angle: f64) -> SpotLight {
+        let cone = 2.0 * PI * (1.0 - (inner_angle.cos() + outer_angle.cos()) / 2.0);
+        let intensity = units::watts_from_lumens(lumens) / cone * units::luminance_scale(&color);
+        SpotLight::new(position, direction, color, intensity, inner_angle, outer_angle)
+    }
+    /// Spot light with a peak intensity of `candela` along its axis.
+    pub fn from_candela(position: Vec3, direction: Vec3, color: Color, candela: f64, inner_angle: f64, outer_angle: f64) -> SpotLight {
+        let intensity = units::watts_per_sr_from_candela(candela) * units::luminance_scale(&color);
+        SpotLight::new(position, direction, color, intensity, inner_angle, outer_angle)
+    }
+    /// Fraction of the intensity emitted along `w`, pointing away from the light.
+    pub fn falloff(&self, w: &Vec3) -> f64 {
+        let cos = w.dot(&self.direction);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        if cos >= cos_inner {
+            return 1.0;
+        }
+        if cos <= cos_outer {
+            return 0.0;
+        }
+        let t = (cos - cos_outer) / (cos_inner - cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, hit: &Hit) -> LightSample {
+        let to_light = self.position - hit.point;
+        let distance = to_light.length();
+        let direction = to_light.scale(1.0 / distance);
+        LightSample {
+            direction,
+            distance,
+            radiance: self.color * (self.intensity * self.falloff(&-direction) / (distance * distance)),
+            pdf: 1.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn power(&self) -> Option<f64> {
+        let cone = 1.0 - (self.inner_angle.cos() + self.outer_angle.cos()) / 2.0;
+        Some(2.0 * PI * cone * self.intensity * self.color.luminance())
+    }
+
+    /// Directions are drawn uniformly within the outer cone.
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let cos_outer = self.outer_angle.cos();
+        let local = sampling::uniform_cone(rand::random(), rand::random(), cos_outer);
+        let direction = Frame::from_normal(&self.direction).to_world(&local);
+        Some(EmissionSample {
+            point: self.position,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            direction,
+            radiance: self.color * (self.intensity * self.falloff(&direction)),
+            pdf_position: 1.0,
+            pdf_direction: sampling::uniform_cone_pdf(cos_outer),
+        })
+    }
+
+    fn pdf_emission(&self, _point: &Vec3, direction: &Vec3) -> (f64, f64) {
+        let cos_outer = self.outer_angle.cos();
+        if direction.normalize().dot(&self.direction) < cos_outer {
+            return (1.0, 0.0);
+        }
+        (1.0, sampling::uniform_cone_pdf(cos_outer))
+    }
+
+    fn emission(&self, _point: &Vec3, direction: &Vec3) -> Color {
+        self.color * (self.intensity * self.falloff(&direction.normalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_falloff() {
+        let light = SpotLight::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            1.0,
+            20.0_f64.to_radians(),
+            40.0_f64.to_radians(),
+        );
+        assert_approx_eq!(light.falloff(&Vec3::new(0.0, -1.0, 0.0)), 1.0);
+        let angle = 30.0_f64.to_radians();
+        let middle = light.falloff(&Vec3::new(angle.sin(), -angle.cos(), 0.0));
+        assert!(middle > 0.0 && middle < 1.0);
+        assert_approx_eq!(light.falloff(&Vec3::new(1.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_sample_and_power_follow_the_cone() {
+        let light = SpotLight::from_candela(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            100.0,
+            20.0_f64.to_radians(),
+            40.0_f64.to_radians(),
+        );
+        let peak = units::watts_per_sr_from_candela(100.0);
+        let at = |w: Vec3| light.sample(&Hit {
+            point: w * 2.0,
+            ..Hit::at_origin()
+        }).radiance.luminance();
+        assert_approx_eq!(at(Vec3::new(0.0, -1.0, 0.0)), peak / 4.0);
+        let angle = 30.0_f64.to_radians();
+        let w = Vec3::new(angle.sin(), -angle.cos(), 0.0);
+        assert_approx_eq!(at(w), peak * light.falloff(&w) / 4.0);
+        assert_approx_eq!(at(Vec3::new(1.0, 0.0, 0.0)), 0.0);
+        // Midpoint rule over the angle from the axis.
+        let steps = 2000;
+        let integral = (0..steps).map(|i| {
+            let theta = PI * (i as f64 + 0.5) / steps as f64;
+            let w = Vec3::new(theta.sin(), -theta.cos(), 0.0);
+            2.0 * PI * theta.sin() * light.falloff(&w) * PI / steps as f64
+        }).sum::<f64>();
+        assert_approx_eq!(light.power().unwrap(), peak * integral, 1e-6);
+    }
+}
@@ -0,0 +1,120 @@
+use std::f64::consts::PI;
+use crate::image::Color;
+
+/// Lumens per watt of radiant power at 555 nm, relating photometric units to
+/// the radiometric ones lights are rendered in.
+pub const LUMINOUS_EFFICACY: f64 = 683.0;
+
+pub fn watts_from_lumens(lumens: f64) -> f64 {
+    lumens / LUMINOUS_EFFICACY
+}
+
+/// Radiant intensity in watts per steradian of a source of `candela`, that is
+/// lumens per steradian.
+pub fn watts_per_sr_from_candela(candela: f64) -> f64 {
+    candela / LUMINOUS_EFFICACY
+}
+
+/// Irradiance in watts per square metre of an illuminance of `lux`, that is
+/// lumens per square metre.
+pub fn watts_per_m2_from_lux(lux: f64) -> f64 {
+    lux / LUMINOUS_EFFICACY
+}
+
+/// Scale turning `color` into a factor of unit luminance, so a light's
+/// strength alone sets how bright it is whatever its hue.
+pub fn luminance_scale(color: &Color) -> f64 {
+    let luminance = color.luminance();
+    if luminance > 0.0 { 1.0 / luminance } else { 0.0 }
+}
+
+/// Piecewise Gaussian used by the CIE 1931 matching function fit of Wyman,
+/// Sloan and Shirley (2013).
+fn lobe(wavelength: f64, mean: f64, below: f64, above: f64) -> f64 {
+    let sigma = if wavelength < mean { below } else { above };
+    let t = (wavelength - mean) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+/// Spectral radiance of a blackbody at `wavelength` in nanometres.
+fn planck(wavelength: f64, kelvin: f64) -> f64 {
+    const H: f64 = 6.62607015e-34;
+    const C: f64 = 2.99792458e8;
+    const K: f64 = 1.380649e-23;
+    let lambda = wavelength * 1e-9;
+    2.0 * H * C * C / (lambda.powi(5) * ((H * C / (lambda * K * kelvin)).exp() - 1.0))
+}
+
+/// Linear sRGB color of a blackbody radiator at `kelvin`, normalized to unit
+/// luminance: about 1900 K for candlelight, 2700 K for incandescent bulbs and
+/// 6500 K for daylight.
+pub fn blackbody(kelvin: f64) -> Color {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for step in 0..=80 {
+        let wavelength = 380.0 + 5.0 * step as f64;
+        let radiance = planck(wavelength, kelvin.max(1.0));
+        x += radiance * (1.056 * lobe(wavelength, 599.8, 37.9, 31.0) + 0.362 * lobe(wavelength, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(wavelength, 501.1, 20.4, 26.2));
+        y += radiance * (0.821 * lobe(wavelength, 568.8, 46.9, 40.5) + 0.286 * lobe(wavelength, 530.9, 16.3, 31.1));
+        z += radiance * (1.217 * lobe(wavelength, 437.0, 11.8, 36.0) + 0.681 * lobe(wavelength, 459.0, 26.0, 13.8));
+    }
+    let color = Color::new(
+        (3.2406 * x - 1.5372 * y - 0.4986 * z).max(0.0),
+        (-0.9689 * x + 1.8758 * y + 0.0415 * z).max(0.0),
+        (0.0557 * x - 0.2040 * y + 1.0570 * z).max(0.0),
+    );
+    color * luminance_scale(&color)
+}
+
+/// Radiance of a diffuse emitter of `area` sending out `watts` in total.
+pub fn radiance_from_watts(watts: f64, area: f64) -> f64 {
+    watts / (PI * area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        vec3::Vec3,
+        geometry::Hit,
+        light::{Light, point_source::PointSource, directional::DirectionalLight, area::RectLight},
+    };
+
+    #[test]
+    fn test_blackbody() {
+        let daylight = blackbody(6500.0);
+        assert_approx_eq!(daylight.luminance(), 1.0);
+        assert_approx_eq!(daylight.red, daylight.blue, 0.1);
+        let incandescent = blackbody(2700.0);
+        assert!(incandescent.red > incandescent.green && incandescent.green > incandescent.blue);
+        assert!(blackbody(10000.0).blue > blackbody(10000.0).red);
+    }
+
+    #[test]
+    fn test_lights_emit_their_rated_power() {
+        let color = blackbody(2700.0);
+        let bulb = PointSource::from_lumens(Vec3::new(0.0, 0.0, 0.0), color, 800.0);
+        assert_approx_eq!(bulb.power().unwrap(), 800.0 / LUMINOUS_EFFICACY);
+        let panel = RectLight::from_watts(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.5, 0.0),
+            color,
+            40.0,
+        );
+        assert_approx_eq!(panel.power().unwrap(), 40.0);
+    }
+
+    #[test]
+    fn test_photometric_lights_deliver_their_illuminance() {
+        let color = blackbody(4000.0);
+        let hit = Hit::at_origin();
+        let bulb = PointSource::from_candela(Vec3::new(0.0, 0.0, 2.0), color, 100.0);
+        let sample = bulb.sample(&hit);
+        assert_approx_eq!(sample.radiance.luminance() / sample.pdf, 100.0 / 4.0 / LUMINOUS_EFFICACY);
+        let sun = DirectionalLight::from_lux(Vec3::new(0.0, 0.0, -1.0), color, 100_000.0);
+        let sample = sun.sample(&hit);
+        assert_approx_eq!(sample.radiance.luminance() / sample.pdf, 100_000.0 / LUMINOUS_EFFICACY);
+    }
+}
@@ -1,23 +1,15 @@
-mod tracer;
-mod ray;
-mod camera;
-mod scene;
-mod image;
-mod vec3;
-mod light;
-mod object;
-mod material;
-mod geometry;
-
-use crate::{
+use std::sync::Arc;
+use rust_tracer::{
     tracer::Tracer,
+    integrator,
     scene::Scene,
     camera::Camera,
     vec3::Vec3,
     object::Object,
-    material::{lambertian::Lambertian},
+    material::{lambertian::Lambertian, conductor::Conductor},
     geometry::{sphere::Sphere, plane::Plane},
     image::Color, light::point_source::PointSource,
+    texture::{constant::Constant, checker::Checker2d},
 };
 
 fn main() {
@@ -35,7 +27,7 @@ fn main() {
     scene.add_object(
         Object::new(
             Box::new(
-                Lambertian::new(Color::new(0.2, 0.2, 0.8)),
+                Conductor::gold(0.3),
             ),
             Box::new(
                 Sphere::new(Vec3::new(0.6, 0.6, -0.6), 0.2),
@@ -45,7 +37,12 @@ fn main() {
     scene.add_object(
         Object::new(
             Box::new(
-                Lambertian::new(Color::new(0.1, 0.8, 0.1)),
+                Lambertian::textured(Arc::new(Checker2d::new(
+                    Arc::new(Constant::new(Color::new(0.1, 0.8, 0.1))),
+                    Arc::new(Constant::new(Color::new(0.8, 0.8, 0.8))),
+                    2.0,
+                    2.0,
+                ))),
             ),
             Box::new(
                 Plane::new(Vec3::new(0.0, 1.0, 0.0), -1.0),
@@ -57,7 +54,7 @@ fn main() {
             PointSource::new(
                 Vec3::new(2.0, 2.0, -2.0),
                 Color::new(1.0, 1.0, 1.0),
-                5.0,
+                15.0,
             ),
         )
     );
@@ -66,7 +63,7 @@ fn main() {
             PointSource::new(
                 Vec3::new(-10.0, 10.0, 5.0),
                 Color::new(1.0, 1.0, 1.0),
-                50.0,
+                150.0,
             ),
         )
     );
@@ -75,7 +72,7 @@ fn main() {
             PointSource::new(
                 Vec3::new(0.0, 0.0, -3.0),
                 Color::new(1.0, 1.0, 1.0),
-                1.0,
+                3.0,
             ),
         )
     );
@@ -88,13 +85,22 @@ fn main() {
         1.0,
     );
 
-    let tracer = Tracer::new(
+    let name = std::env::args().nth(1).unwrap_or_else(|| "path".to_string());
+    let integrator = match integrator::from_name(&name, 10) {
+        Some(integrator) => integrator,
+        None => {
+            eprintln!("unknown integrator: {}", name);
+            std::process::exit(1);
+        }
+    };
+
+    let mut tracer = Tracer::new(
         scene,
         camera,
+        integrator,
         1000,
         1000,
         10,
-        10,
     );
     let image = tracer.render();
     image.to_image().save("output.png").unwrap();
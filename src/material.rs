@@ -1,16 +1,95 @@
 pub mod lambertian;
+pub mod oren_nayar;
+pub mod microfacet;
+pub mod conductor;
+pub mod dielectric;
+pub mod principled;
+pub mod bump;
+pub mod mix;
+pub mod layered;
+pub mod subsurface;
+pub mod cutout;
+pub mod emissive;
 
 use crate::{
     ray::Ray,
     image::Color,
-    light::Light,
-    geometry::Hit,
+    light::LightSample,
+    geometry::{Geometry, Hit},
+    vec3::Vec3,
 };
 
+/// Direction drawn from a material's scattering distribution.
+/// `value` is the BSDF value for `direction` and `pdf` its solid angle density.
+/// For specular samples `pdf` is the probability of having picked that lobe,
+/// such as the Fresnel reflectance for smooth glass, and `value` carries the
+/// delta term, so only `value / pdf` is meaningful.
+#[derive(Debug, Clone, Copy)]
+pub struct BsdfSample {
+    pub direction: Vec3,
+    pub value: Color,
+    pub pdf: f64,
+    pub specular: bool,
+    /// Relative index of refraction η_t / η_i for samples passing through the
+    /// surface, 1 for reflection. `value` scales radiance by 1 / eta², which
+    /// particles carrying power rather than radiance multiply back out.
+    pub eta: f64,
+}
+
+/// Where light that entered a translucent object leaves it again, and the
+/// throughput of its path below the surface. Light leaves the exit point with
+/// a cosine-weighted distribution scaled by `weight`.
+#[derive(Debug, Clone)]
+pub struct SubsurfaceExit {
+    pub hit: Hit,
+    pub weight: Color,
+}
+
+/// Scattering at a surface. `wo` points towards the viewer and `wi` towards
+/// the light, both normalized and in world space.
 pub trait Material {
-    fn shade(&self, ray: &Ray, hit: &Hit, lights: &Box<dyn Light>, depth: u32, color: &mut Color);
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color;
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample>;
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64;
+    fn shade(&self, ray: &Ray, sample: &LightSample, hit: &Hit, _depth: u32, color: &mut Color) {
+        let wo = -ray.direction.normalize();
+        let cos = sample.direction.dot(&hit.normal).abs();
+        *color += self.eval(&wo, &sample.direction, hit) * sample.radiance * (cos / sample.pdf);
+    }
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    /// Whether every sample is specular, so `eval` is zero everywhere and the
+    /// surface only redirects light, as smooth glass and mirrors do.
+    fn is_specular(&self) -> bool {
+        false
+    }
+    /// Every specular direction leaving the surface towards `wo`, each weighted
+    /// by `value / pdf` like a sample, so integrators can follow all of them
+    /// instead of drawing one. Defaults to a single specular sample.
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        self.sample(wo, hit).filter(|sample| sample.specular).into_iter().collect()
+    }
+    /// Radiance emitted from `hit` towards `wo`.
+    fn emitted(&self, _wo: &Vec3, _hit: &Hit) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    /// Probability that a ray stops at `hit` rather than passing through it.
+    fn opacity(&self, _hit: &Hit) -> f64 {
+        1.0
+    }
+    /// Light carried straight through the surface along `wi` for shadow rays,
+    /// ignoring refraction. Black for opaque materials.
+    fn transmittance(&self, _wi: &Vec3, _hit: &Hit) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    /// Traces light refracted into `geometry` below the surface, for materials
+    /// that scatter volumetrically.
+    fn subsurface(&self, _wo: &Vec3, _hit: &Hit, _geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        None
+    }
 }
 
 pub fn default() -> Box<lambertian::Lambertian> {
     Box::new(lambertian::Lambertian::new(Color::new(8.0, 8.0, 8.0)))
-}
\ No newline at end of file
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{Material, BsdfSample, SubsurfaceExit},
+    geometry::{Geometry, Hit},
+    texture::Texture,
+    vec3::Vec3,
+};
+
+/// Replaces the hit's shading normal and re-orthogonalizes its tangent.
+fn with_normal(hit: &Hit, normal: Vec3) -> Hit {
+    let mut hit = hit.clone();
+    hit.tangent = hit.tangent - normal * normal.dot(&hit.tangent);
+    hit.normal = normal;
+    hit
+}
+
+/// Perturbs the shading normal of `material` with a tangent-space normal map,
+/// encoded with x along the tangent, y along increasing v and z off the surface.
+/// The map should be loaded without sRGB decoding.
+pub struct NormalMap {
+    pub material: Box<dyn Material>,
+    pub map: Arc<dyn Texture>,
+    pub strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(material: Box<dyn Material>, map: Arc<dyn Texture>, strength: f64) -> NormalMap {
+        NormalMap {
+            material,
+            map,
+            strength,
+        }
+    }
+    pub fn perturb(&self, hit: &Hit) -> Hit {
+        let texel = self.map.value(hit);
+        let local = Vec3::new(
+            (2.0 * texel.red - 1.0) * self.strength,
+            (2.0 * texel.green - 1.0) * self.strength,
+            2.0 * texel.blue - 1.0,
+        );
+        if local.z <= 0.0 {
+            return hit.clone();
+        }
+        with_normal(hit, hit.frame().to_world(&local).normalize())
+    }
+}
+
+impl Material for NormalMap {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.eval(wo, wi, &self.perturb(hit))
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        self.material.sample(wo, &self.perturb(hit))
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        self.material.pdf(wo, wi, &self.perturb(hit))
+    }
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        self.material.specular_lobes(wo, &self.perturb(hit))
+    }
+    fn opacity(&self, hit: &Hit) -> f64 {
+        self.material.opacity(hit)
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.transmittance(wi, &self.perturb(hit))
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        self.material.subsurface(wo, &self.perturb(hit), geometry)
+    }
+}
+
+/// Perturbs the shading normal of `material` by the gradient of a grayscale
+/// height texture, estimated with finite differences of `delta` along the
+/// tangent frame, in both uv and object space.
+pub struct BumpMap {
+    pub material: Box<dyn Material>,
+    pub height: Arc<dyn Texture>,
+    pub strength: f64,
+    pub delta: f64,
+}
+
+impl BumpMap {
+    pub fn new(material: Box<dyn Material>, height: Arc<dyn Texture>, strength: f64) -> BumpMap {
+        BumpMap {
+            material,
+            height,
+            strength,
+            delta: 1e-3,
+        }
+    }
+    fn height_at(&self, hit: &Hit, du: f64, dv: f64, offset: Vec3) -> f64 {
+        let mut shifted = hit.clone();
+        shifted.uv = (hit.uv.0 + du, hit.uv.1 + dv);
+        shifted.point = hit.point + offset;
+        shifted.object_point = hit.object_point + offset;
+        self.height.value(&shifted).luminance()
+    }
+    pub fn perturb(&self, hit: &Hit) -> Hit {
+        let frame = hit.frame();
+        let h = self.height_at(hit, 0.0, 0.0, Vec3::new(0.0, 0.0, 0.0));
+        let dh_du = (self.height_at(hit, self.delta, 0.0, frame.s * self.delta) - h) / self.delta;
+        let dh_dv = (self.height_at(hit, 0.0, self.delta, frame.t * self.delta) - h) / self.delta;
+        let normal = frame.n - (frame.s * dh_du + frame.t * dh_dv) * self.strength;
+        with_normal(hit, normal.normalize())
+    }
+}
+
+impl Material for BumpMap {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.eval(wo, wi, &self.perturb(hit))
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        self.material.sample(wo, &self.perturb(hit))
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        self.material.pdf(wo, wi, &self.perturb(hit))
+    }
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        self.material.specular_lobes(wo, &self.perturb(hit))
+    }
+    fn opacity(&self, hit: &Hit) -> f64 {
+        self.material.opacity(hit)
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.transmittance(wi, &self.perturb(hit))
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        self.material.subsurface(wo, &self.perturb(hit), geometry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        material::lambertian::Lambertian,
+        texture::constant::Constant,
+    };
+
+    #[test]
+    fn test_flat_normal_map_keeps_normal() {
+        let map = NormalMap::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Arc::new(Constant::new(Color::new(0.5, 0.5, 1.0))),
+            1.0,
+        );
+        let perturbed = map.perturb(&Hit::at_origin());
+        assert_approx_eq!(perturbed.normal.z, 1.0);
+    }
+
+    #[test]
+    fn test_normal_map_tilts_towards_tangent() {
+        let map = NormalMap::new(
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Arc::new(Constant::new(Color::new(1.0, 0.5, 1.0))),
+            1.0,
+        );
+        let perturbed = map.perturb(&Hit::at_origin());
+        assert_approx_eq!(perturbed.normal.x, 0.5_f64.sqrt());
+        assert_approx_eq!(perturbed.normal.z, 0.5_f64.sqrt());
+        assert_approx_eq!(perturbed.tangent.dot(&perturbed.normal), 0.0);
+    }
+}
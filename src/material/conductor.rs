@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{
+        Material,
+        BsdfSample,
+        microfacet::{self, Ggx},
+    },
+    geometry::Hit,
+    vec3::Vec3,
+    texture::{Texture, constant::Constant},
+};
+
+/// Rough metal using a GGX microfacet distribution and the exact conductor
+/// Fresnel term for a complex index of refraction `eta + i k`.
+#[derive(Debug, Clone)]
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: Ggx,
+    /// Filter on the reflected light, for painted or patterned metal.
+    pub tint: Arc<dyn Texture>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Conductor {
+        Conductor::anisotropic(eta, k, roughness, roughness)
+    }
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            distribution: Ggx::from_roughness(roughness_u, roughness_v),
+            tint: Arc::new(Constant::new(Color::new(1.0, 1.0, 1.0))),
+        }
+    }
+    pub fn with_tint(self, tint: Color) -> Conductor {
+        self.with_tint_texture(Arc::new(Constant::new(tint)))
+    }
+    pub fn with_tint_texture(mut self, tint: Arc<dyn Texture>) -> Conductor {
+        self.tint = tint;
+        self
+    }
+    pub fn gold(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.385, 1.603), roughness)
+    }
+    pub fn copper(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness)
+    }
+    pub fn aluminum(roughness: f64) -> Conductor {
+        Conductor::new(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+    }
+}
+
+impl Material for Conductor {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        let (wo, wi) = hit.frame().to_local_facing(wo, wi);
+        if self.distribution.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let m = (wo + wi).normalize();
+        let fresnel = microfacet::fresnel_conductor(wo.dot(&m), &self.eta, &self.k);
+        self.tint.value(hit) * fresnel * (self.distribution.d(&m) * self.distribution.g(&wo, &wi) / (4.0 * wo.z * wi.z))
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame().facing(wo);
+        let wo_local = frame.to_local(wo);
+        if wo_local.z <= 0.0 {
+            return None;
+        }
+        if self.distribution.is_smooth() {
+            let wi = Vec3::new(-wo_local.x, -wo_local.y, wo_local.z);
+            let fresnel = microfacet::fresnel_conductor(wo_local.z, &self.eta, &self.k);
+            return Some(BsdfSample {
+                direction: frame.to_world(&wi),
+                value: self.tint.value(hit) * fresnel * (1.0 / wo_local.z),
+                pdf: 1.0,
+                specular: true,
+                eta: 1.0,
+            });
+        }
+        let m = self.distribution.sample_visible(&wo_local, rand::random(), rand::random());
+        let wi = microfacet::reflect(&wo_local, &m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let direction = frame.to_world(&wi);
+        Some(BsdfSample {
+            direction,
+            value: self.eval(wo, &direction, hit),
+            pdf: self.distribution.d_visible(&wo_local, &m) / (4.0 * wo_local.dot(&m)),
+            specular: false,
+            eta: 1.0,
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        let (wo, wi) = hit.frame().to_local_facing(wo, wi);
+        if self.distribution.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let m = (wo + wi).normalize();
+        self.distribution.d_visible(&wo, &m) / (4.0 * wo.dot(&m))
+    }
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_sampled_albedo_matches_quadrature() {
+        let material = Conductor::anisotropic(Color::new(0.2, 0.9, 1.1), Color::new(3.9, 2.4, 2.1), 0.4, 0.7);
+        let hit = Hit::at_origin();
+        let wo = Vec3::new(0.3, 0.2, 0.9).normalize();
+        let n = 400;
+        let mut quadrature = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let cos_theta = (i as f64 + 0.5) / n as f64;
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let wi = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                quadrature += material.eval(&wo, &wi, &hit).green * cos_theta;
+            }
+        }
+        quadrature *= 2.0 * PI / (n * n) as f64;
+        let samples = 200_000;
+        let mut estimate = 0.0;
+        for _ in 0..samples {
+            if let Some(sample) = material.sample(&wo, &hit) {
+                assert_approx_eq!(sample.pdf, material.pdf(&wo, &sample.direction, &hit), 1e-9);
+                estimate += sample.value.green * sample.direction.z / sample.pdf;
+            }
+        }
+        estimate /= samples as f64;
+        assert_approx_eq!(estimate, quadrature, 1e-2);
+        assert!(quadrature < 1.0);
+    }
+
+    #[test]
+    fn test_tint_filters_reflection() {
+        let plain = Conductor::copper(0.3);
+        let tinted = Conductor::copper(0.3).with_tint(Color::new(0.5, 1.0, 0.0));
+        let wo = Vec3::new(0.3, 0.0, 0.9).normalize();
+        let wi = Vec3::new(-0.2, 0.1, 0.9).normalize();
+        let (plain, tinted) = (plain.eval(&wo, &wi, &Hit::at_origin()), tinted.eval(&wo, &wi, &Hit::at_origin()));
+        assert_approx_eq!(tinted.red, plain.red * 0.5);
+        assert_approx_eq!(tinted.green, plain.green);
+        assert_approx_eq!(tinted.blue, 0.0);
+    }
+
+    #[test]
+    fn test_smooth_conductor_is_a_mirror() {
+        let material = Conductor::gold(0.0);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let sample = material.sample(&wo, &Hit::at_origin()).unwrap();
+        assert!(sample.specular);
+        assert_approx_eq!(sample.direction.x, -0.6);
+        assert_approx_eq!(sample.direction.z, 0.8);
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{Material, BsdfSample, SubsurfaceExit},
+    geometry::{Geometry, Hit},
+    texture::{Texture, constant::Constant},
+    vec3::Vec3,
+};
+
+/// Masks `material` with an opacity in `[0, 1]`, the luminance of `opacity`.
+/// Rays of every kind pass through where it is zero, as with alpha-masked
+/// leaves, fences or decals; fractional values are stochastic.
+pub struct Cutout {
+    pub material: Box<dyn Material>,
+    pub opacity: Arc<dyn Texture>,
+}
+
+impl Cutout {
+    pub fn new(material: Box<dyn Material>, opacity: f64) -> Cutout {
+        Cutout::textured(material, Arc::new(Constant::new(Color::new(opacity, opacity, opacity))))
+    }
+    pub fn textured(material: Box<dyn Material>, opacity: Arc<dyn Texture>) -> Cutout {
+        Cutout {
+            material,
+            opacity,
+        }
+    }
+}
+
+impl Material for Cutout {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.eval(wo, wi, hit)
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        self.material.sample(wo, hit)
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        self.material.pdf(wo, wi, hit)
+    }
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        self.material.specular_lobes(wo, hit)
+    }
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+    fn emitted(&self, wo: &Vec3, hit: &Hit) -> Color {
+        self.material.emitted(wo, hit)
+    }
+    fn opacity(&self, hit: &Hit) -> f64 {
+        self.opacity.value(hit).luminance().clamp(0.0, 1.0) * self.material.opacity(hit)
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        self.material.transmittance(wi, hit)
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        self.material.subsurface(wo, hit, geometry)
+    }
+}
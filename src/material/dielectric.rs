@@ -0,0 +1,249 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{
+        Material,
+        BsdfSample,
+        microfacet::{self, Ggx},
+    },
+    geometry::Hit,
+    vec3::Vec3,
+    texture::{Texture, constant::Constant},
+};
+
+/// Glass-like interface with GGX roughness. The hit normal is taken to point
+/// out of the object, into the medium with index 1. Transmitted light is
+/// filtered by `tint`.
+#[derive(Debug, Clone)]
+pub struct Dielectric {
+    pub ior: f64,
+    pub distribution: Ggx,
+    pub tint: Arc<dyn Texture>,
+}
+
+impl Dielectric {
+    pub fn new(ior: f64, roughness: f64) -> Dielectric {
+        Dielectric::anisotropic(ior, roughness, roughness)
+    }
+    pub fn anisotropic(ior: f64, roughness_u: f64, roughness_v: f64) -> Dielectric {
+        Dielectric {
+            ior,
+            distribution: Ggx::from_roughness(roughness_u, roughness_v),
+            tint: Arc::new(Constant::new(Color::new(1.0, 1.0, 1.0))),
+        }
+    }
+    pub fn with_tint(self, tint: Color) -> Dielectric {
+        self.with_tint_texture(Arc::new(Constant::new(tint)))
+    }
+    pub fn with_tint_texture(mut self, tint: Arc<dyn Texture>) -> Dielectric {
+        self.tint = tint;
+        self
+    }
+    /// Generalized half vector for a local direction pair, facing +z, along
+    /// with the relative index used for it.
+    fn half_vector(&self, wo: &Vec3, wi: &Vec3) -> Option<(Vec3, f64)> {
+        let reflect = wo.z * wi.z > 0.0;
+        let etap = if reflect {
+            1.0
+        } else if wo.z > 0.0 {
+            self.ior
+        } else {
+            1.0 / self.ior
+        };
+        let m = *wi * etap + *wo;
+        if wo.z == 0.0 || wi.z == 0.0 || m.dot(&m) == 0.0 {
+            return None;
+        }
+        let mut m = m.normalize();
+        if m.z < 0.0 {
+            m = -m;
+        }
+        if m.dot(wi) * wi.z < 0.0 || m.dot(wo) * wo.z < 0.0 {
+            return None;
+        }
+        Some((m, etap))
+    }
+}
+
+impl Material for Dielectric {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let frame = hit.frame();
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        let (m, etap) = match self.half_vector(&wo, &wi) {
+            Some(half) => half,
+            None => return Color::new(0.0, 0.0, 0.0),
+        };
+        let fresnel = microfacet::fresnel_dielectric(wo.dot(&m), self.ior);
+        let d = self.distribution.d(&m);
+        let g = self.distribution.g(&wo, &wi);
+        if wo.z * wi.z > 0.0 {
+            let value = d * g * fresnel / (4.0 * wi.z * wo.z).abs();
+            Color::new(value, value, value)
+        } else {
+            let denom = (wi.dot(&m) + wo.dot(&m) / etap).powi(2) * wi.z * wo.z;
+            self.tint.value(hit) * (d * (1.0 - fresnel) * g * (wi.dot(&m) * wo.dot(&m) / denom).abs() / (etap * etap))
+        }
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame();
+        let wo_local = frame.to_local(wo);
+        if wo_local.z == 0.0 {
+            return None;
+        }
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let m = if self.distribution.is_smooth() {
+            normal
+        } else {
+            self.distribution.sample_visible(&wo_local, rand::random(), rand::random())
+        };
+        let reflectance = microfacet::fresnel_dielectric(wo_local.dot(&m), self.ior);
+        if rand::random::<f64>() < reflectance {
+            let wi = microfacet::reflect(&wo_local, &m);
+            if wi.z * wo_local.z <= 0.0 {
+                return None;
+            }
+            if self.distribution.is_smooth() {
+                let value = reflectance / wi.z.abs();
+                return Some(BsdfSample {
+                    direction: frame.to_world(&wi),
+                    value: Color::new(value, value, value),
+                    pdf: reflectance,
+                    specular: true,
+                    eta: 1.0,
+                });
+            }
+            let direction = frame.to_world(&wi);
+            Some(BsdfSample {
+                direction,
+                value: self.eval(wo, &direction, hit),
+                pdf: self.distribution.d_visible(&wo_local, &m) / (4.0 * wo_local.dot(&m).abs()) * reflectance,
+                specular: false,
+                eta: 1.0,
+            })
+        } else {
+            let (wi, etap) = microfacet::refract(&wo_local, &m, self.ior)?;
+            if wi.z * wo_local.z >= 0.0 {
+                return None;
+            }
+            if self.distribution.is_smooth() {
+                let value = (1.0 - reflectance) / (wi.z.abs() * etap * etap);
+                return Some(BsdfSample {
+                    direction: frame.to_world(&wi),
+                    value: self.tint.value(hit) * value,
+                    pdf: 1.0 - reflectance,
+                    specular: true,
+                    eta: etap,
+                });
+            }
+            let denom = (wi.dot(&m) + wo_local.dot(&m) / etap).powi(2);
+            let dm_dwi = wi.dot(&m).abs() / denom;
+            let direction = frame.to_world(&wi);
+            Some(BsdfSample {
+                direction,
+                value: self.eval(wo, &direction, hit),
+                pdf: self.distribution.d_visible(&wo_local, &m) * dm_dwi * (1.0 - reflectance),
+                specular: false,
+                eta: etap,
+            })
+        }
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let frame = hit.frame();
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        let (m, etap) = match self.half_vector(&wo, &wi) {
+            Some(half) => half,
+            None => return 0.0,
+        };
+        let reflectance = microfacet::fresnel_dielectric(wo.dot(&m), self.ior);
+        if wo.z * wi.z > 0.0 {
+            self.distribution.d_visible(&wo, &m) / (4.0 * wo.dot(&m).abs()) * reflectance
+        } else {
+            let denom = (wi.dot(&m) + wo.dot(&m) / etap).powi(2);
+            let dm_dwi = wi.dot(&m).abs() / denom;
+            self.distribution.d_visible(&wo, &m) * dm_dwi * (1.0 - reflectance)
+        }
+    }
+    /// Both the reflected and the refracted direction of a smooth interface.
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        let frame = hit.frame();
+        let wo_local = frame.to_local(wo);
+        if !self.distribution.is_smooth() || wo_local.z == 0.0 {
+            return Vec::new();
+        }
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let reflectance = microfacet::fresnel_dielectric(wo_local.z, self.ior);
+        let reflected = microfacet::reflect(&wo_local, &normal);
+        let value = reflectance / reflected.z.abs();
+        let mut lobes = vec![BsdfSample {
+            direction: frame.to_world(&reflected),
+            value: Color::new(value, value, value),
+            pdf: 1.0,
+            specular: true,
+            eta: 1.0,
+        }];
+        if let Some((refracted, etap)) = microfacet::refract(&wo_local, &normal, self.ior) {
+            if reflectance < 1.0 {
+                lobes.push(BsdfSample {
+                    direction: frame.to_world(&refracted),
+                    value: self.tint.value(hit) * ((1.0 - reflectance) / (refracted.z.abs() * etap * etap)),
+                    pdf: 1.0,
+                    specular: true,
+                    eta: etap,
+                });
+            }
+        }
+        lobes
+    }
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        self.tint.value(hit) * (1.0 - microfacet::fresnel_dielectric(wi.dot(&hit.normal), self.ior))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_sample_pdf_matches_pdf() {
+        let material = Dielectric::new(1.5, 0.5);
+        let hit = Hit::at_origin();
+        for wo in [Vec3::new(0.3, 0.1, 0.9).normalize(), Vec3::new(0.2, -0.4, -0.7).normalize()] {
+            let mut transmitted = 0;
+            for _ in 0..1000 {
+                if let Some(sample) = material.sample(&wo, &hit) {
+                    assert_approx_eq!(sample.pdf, material.pdf(&wo, &sample.direction, &hit), 1e-6);
+                    let value = material.eval(&wo, &sample.direction, &hit);
+                    assert_approx_eq!(sample.value.red, value.red, 1e-9);
+                    if sample.direction.z * wo.z < 0.0 {
+                        transmitted += 1;
+                    }
+                }
+            }
+            assert!(transmitted > 0);
+        }
+    }
+    #[test]
+    fn test_refraction_conserves_power_at_normal_incidence() {
+        let material = Dielectric::new(1.5, 0.0);
+        let hit = Hit::at_origin();
+        let transmittance = 1.0 - microfacet::fresnel_dielectric(1.0, 1.5);
+        for wo in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0)] {
+            let lobes = material.specular_lobes(&wo, &hit);
+            let refracted = lobes.iter().find(|lobe| lobe.direction.z * wo.z < 0.0).unwrap();
+            let power = refracted.value.red * refracted.eta * refracted.eta / refracted.pdf;
+            assert_approx_eq!(power, transmittance, 1e-9);
+        }
+    }
+}
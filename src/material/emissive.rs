@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{Material, BsdfSample},
+    geometry::Hit,
+    texture::{Texture, constant::Constant},
+    vec3::Vec3,
+};
+
+/// Light-emitting surface that turns its object into an area light. Emits
+/// `radiance` times `strength` from the side its normal faces, and reflects nothing.
+#[derive(Debug, Clone)]
+pub struct Emissive {
+    pub radiance: Arc<dyn Texture>,
+    pub strength: f64,
+}
+
+impl Emissive {
+    pub fn new(radiance: Color, strength: f64) -> Emissive {
+        Emissive::textured(Arc::new(Constant::new(radiance)), strength)
+    }
+    pub fn textured(radiance: Arc<dyn Texture>, strength: f64) -> Emissive {
+        Emissive {
+            radiance,
+            strength,
+        }
+    }
+}
+
+impl Material for Emissive {
+    fn eval(&self, _wo: &Vec3, _wi: &Vec3, _hit: &Hit) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    fn sample(&self, _wo: &Vec3, _hit: &Hit) -> Option<BsdfSample> {
+        None
+    }
+    fn pdf(&self, _wo: &Vec3, _wi: &Vec3, _hit: &Hit) -> f64 {
+        0.0
+    }
+    fn is_emissive(&self) -> bool {
+        true
+    }
+    fn emitted(&self, wo: &Vec3, hit: &Hit) -> Color {
+        if wo.dot(&hit.normal) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.radiance.value(hit) * self.strength
+    }
+}
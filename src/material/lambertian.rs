@@ -1,18 +1,26 @@
+use std::{
+    f64::consts::PI,
+    sync::Arc,
+};
 use crate::{
     image::Color,
-    ray::Ray,
-    material::Material,
-    light::Light,
+    material::{Material, BsdfSample},
     geometry::Hit,
+    vec3::Vec3,
+    sampling,
+    texture::{Texture, constant::Constant},
 };
 
 #[derive(Debug, Clone)]
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Lambertian {
+        Lambertian::textured(Arc::new(Constant::new(albedo)))
+    }
+    pub fn textured(albedo: Arc<dyn Texture>) -> Lambertian {
         Lambertian {
             albedo,
         }
@@ -20,7 +28,31 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn shade(&self, ray: &Ray, hit: &Hit, light: &Box<dyn Light>, _depth: u32, color: &mut Color) {
-        *color += self.albedo * light.intensity(ray, hit);
-    }   
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        if wo.dot(&hit.normal) * wi.dot(&hit.normal) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.albedo.value(hit) * (1.0 / PI)
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame();
+        let mut local = sampling::cosine_hemisphere(rand::random(), rand::random());
+        if wo.dot(&hit.normal) < 0.0 {
+            local.z = -local.z;
+        }
+        let direction = frame.to_world(&local);
+        Some(BsdfSample {
+            direction,
+            value: self.eval(wo, &direction, hit),
+            pdf: sampling::cosine_hemisphere_pdf(local.z),
+            specular: false,
+            eta: 1.0,
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        if wo.dot(&hit.normal) * wi.dot(&hit.normal) <= 0.0 {
+            return 0.0;
+        }
+        sampling::cosine_hemisphere_pdf(wi.dot(&hit.normal))
+    }
 }
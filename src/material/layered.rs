@@ -0,0 +1,281 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{
+        Material,
+        BsdfSample,
+        SubsurfaceExit,
+        microfacet::{self, Ggx},
+    },
+    geometry::{Geometry, Hit},
+    vec3::Vec3,
+    texture::{Texture, constant::Constant},
+};
+
+/// Dielectric coating over an arbitrary base material, as in varnish or clearcoat.
+///
+/// Light reaching the base is what the coating's Fresnel term lets through on
+/// the way in and on the way out, and may be tinted by absorption inside the
+/// coating, so the coating and base together never reflect more than arrives.
+pub struct Layered {
+    pub base: Box<dyn Material>,
+    pub ior: f64,
+    pub distribution: Ggx,
+    /// Absorption coefficient of the coating, per unit of `thickness`,
+    /// which gives the coating its color.
+    pub absorption: Arc<dyn Texture>,
+    pub thickness: f64,
+}
+
+impl Layered {
+    pub fn new(base: Box<dyn Material>, ior: f64, roughness: f64) -> Layered {
+        Layered {
+            base,
+            ior,
+            distribution: Ggx::from_roughness(roughness, roughness),
+            absorption: Arc::new(Constant::new(Color::new(0.0, 0.0, 0.0))),
+            thickness: 0.0,
+        }
+    }
+    pub fn with_absorption(self, absorption: Color, thickness: f64) -> Layered {
+        self.with_absorption_texture(Arc::new(Constant::new(absorption)), thickness)
+    }
+    pub fn with_absorption_texture(mut self, absorption: Arc<dyn Texture>, thickness: f64) -> Layered {
+        self.absorption = absorption;
+        self.thickness = thickness;
+        self
+    }
+    /// Fraction of light passing through the coating on both legs of the path.
+    fn coupling(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        self.transmission(wo.z, hit) * self.transmission(wi.z, hit)
+    }
+    /// Fraction of light crossing the coating once at `cos` from the normal.
+    fn transmission(&self, cos: f64, hit: &Hit) -> Color {
+        let transmittance = 1.0 - microfacet::fresnel_dielectric(cos, self.ior);
+        let path = self.thickness / cos;
+        let absorption = self.absorption.value(hit);
+        Color::new(
+            (-absorption.red * path).exp(),
+            (-absorption.green * path).exp(),
+            (-absorption.blue * path).exp(),
+        ) * transmittance
+    }
+    fn eval_coating(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let m = (*wo + *wi).normalize();
+        let fresnel = microfacet::fresnel_dielectric(wo.dot(&m), self.ior);
+        fresnel * self.distribution.d(&m) * self.distribution.g(wo, wi) / (4.0 * wo.z * wi.z)
+    }
+    fn pdf_coating(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let m = (*wo + *wi).normalize();
+        self.distribution.d_visible(wo, &m) / (4.0 * wo.dot(&m))
+    }
+    fn coating_probability(&self, wo: &Vec3) -> f64 {
+        microfacet::fresnel_dielectric(wo.z, self.ior).clamp(0.05, 0.95)
+    }
+}
+
+impl Material for Layered {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        let (wo_local, wi_local) = hit.frame().to_local_facing(wo, wi);
+        if wo_local.z <= 0.0 || wi_local.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let coating = self.eval_coating(&wo_local, &wi_local);
+        self.base.eval(wo, wi, hit) * self.coupling(&wo_local, &wi_local, hit) + Color::new(coating, coating, coating)
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame().facing(wo);
+        let wo_local = frame.to_local(wo);
+        if wo_local.z <= 0.0 {
+            return None;
+        }
+        let probability = self.coating_probability(&wo_local);
+        if rand::random::<f64>() < probability {
+            if self.distribution.is_smooth() {
+                let wi_local = Vec3::new(-wo_local.x, -wo_local.y, wo_local.z);
+                let fresnel = microfacet::fresnel_dielectric(wo_local.z, self.ior);
+                return Some(BsdfSample {
+                    direction: frame.to_world(&wi_local),
+                    value: Color::new(1.0, 1.0, 1.0) * (fresnel / wo_local.z),
+                    pdf: probability,
+                    specular: true,
+                    eta: 1.0,
+                });
+            }
+            let m = self.distribution.sample_visible(&wo_local, rand::random(), rand::random());
+            let wi_local = microfacet::reflect(&wo_local, &m);
+            if wi_local.z <= 0.0 {
+                return None;
+            }
+            let direction = frame.to_world(&wi_local);
+            let pdf = self.pdf(wo, &direction, hit);
+            return Some(BsdfSample {
+                direction,
+                value: self.eval(wo, &direction, hit),
+                pdf,
+                specular: false,
+                eta: 1.0,
+            });
+        }
+        let sample = self.base.sample(wo, hit)?;
+        let wi_local = frame.to_local(&sample.direction);
+        if wi_local.z <= 0.0 {
+            return None;
+        }
+        if sample.specular {
+            return Some(BsdfSample {
+                value: sample.value * self.coupling(&wo_local, &wi_local, hit),
+                pdf: sample.pdf * (1.0 - probability),
+                ..sample
+            });
+        }
+        let pdf = self.pdf(wo, &sample.direction, hit);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            value: self.eval(wo, &sample.direction, hit),
+            pdf,
+            ..sample
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        let (wo_local, wi_local) = hit.frame().to_local_facing(wo, wi);
+        if wo_local.z <= 0.0 || wi_local.z <= 0.0 {
+            return 0.0;
+        }
+        let probability = self.coating_probability(&wo_local);
+        probability * self.pdf_coating(&wo_local, &wi_local) + (1.0 - probability) * self.base.pdf(wo, wi, hit)
+    }
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth() && self.base.is_specular()
+    }
+    /// The smooth coating's reflection alongside the base's lobes seen through it.
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        let frame = hit.frame().facing(wo);
+        let wo_local = frame.to_local(wo);
+        if wo_local.z <= 0.0 {
+            return Vec::new();
+        }
+        let mut lobes = Vec::new();
+        if self.distribution.is_smooth() {
+            let wi_local = Vec3::new(-wo_local.x, -wo_local.y, wo_local.z);
+            let fresnel = microfacet::fresnel_dielectric(wo_local.z, self.ior);
+            lobes.push(BsdfSample {
+                direction: frame.to_world(&wi_local),
+                value: Color::new(1.0, 1.0, 1.0) * (fresnel / wo_local.z),
+                pdf: 1.0,
+                specular: true,
+                eta: 1.0,
+            });
+        }
+        for lobe in self.base.specular_lobes(wo, hit) {
+            let wi_local = frame.to_local(&lobe.direction);
+            if wi_local.z > 0.0 {
+                lobes.push(BsdfSample {
+                    value: lobe.value * self.coupling(&wo_local, &wi_local, hit),
+                    ..lobe
+                });
+            }
+        }
+        lobes
+    }
+    fn opacity(&self, hit: &Hit) -> f64 {
+        self.base.opacity(hit)
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        let coating = 1.0 - microfacet::fresnel_dielectric(wi.dot(&hit.normal).abs(), self.ior);
+        self.base.transmittance(wi, hit) * coating
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        let cos = wo.dot(&hit.normal).abs();
+        if cos <= 0.0 {
+            return None;
+        }
+        let exit = self.base.subsurface(wo, hit, geometry)?;
+        Some(SubsurfaceExit {
+            weight: exit.weight * self.transmission(cos, hit),
+            ..exit
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::{
+        material::{lambertian::Lambertian, subsurface::Subsurface},
+        geometry::sphere::Sphere,
+        ray::Ray,
+    };
+
+    #[test]
+    fn test_coating_over_white_base_conserves_energy() {
+        let material = Layered::new(Box::new(Lambertian::new(Color::new(1.0, 1.0, 1.0))), 1.5, 0.3);
+        let hit = Hit::at_origin();
+        for wo in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.8, 0.0, 0.6), Vec3::new(0.99, 0.0, 0.141).normalize()] {
+            let samples = 50_000;
+            let mut albedo = 0.0;
+            for _ in 0..samples {
+                if let Some(sample) = material.sample(&wo, &hit) {
+                    albedo += sample.value.green * sample.direction.z.abs() / sample.pdf;
+                }
+            }
+            albedo /= samples as f64;
+            assert!(albedo > 0.5 && albedo <= 1.01, "albedo {}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_coating_without_index_contrast_leaves_base() {
+        let base = Lambertian::new(Color::new(0.6, 0.5, 0.4));
+        let material = Layered::new(Box::new(base.clone()), 1.0, 0.3);
+        let hit = Hit::at_origin();
+        let wo = Vec3::new(0.3, 0.1, 0.9).normalize();
+        for wi in [Vec3::new(-0.3, -0.1, 0.9).normalize(), Vec3::new(0.7, 0.0, 0.2).normalize()] {
+            let (value, expected) = (material.eval(&wo, &wi, &hit), base.eval(&wo, &wi, &hit));
+            assert_approx_eq!(value.red, expected.red, 1e-12);
+            assert_approx_eq!(value.blue, expected.blue, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_coating_over_black_base_reflects_fresnel() {
+        let material = Layered::new(Box::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))), 1.5, 0.0);
+        let hit = Hit::at_origin();
+        for wo in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.8, 0.0, 0.6)] {
+            let samples = 200_000;
+            let mut albedo = 0.0;
+            for _ in 0..samples {
+                if let Some(sample) = material.sample(&wo, &hit) {
+                    albedo += sample.value.green * sample.direction.z.abs() / sample.pdf;
+                }
+            }
+            albedo /= samples as f64;
+            let fresnel = microfacet::fresnel_dielectric(wo.z, 1.5);
+            assert!((albedo / fresnel - 1.0).abs() < 0.05, "{} vs {}", albedo, fresnel);
+        }
+    }
+
+    #[test]
+    fn test_coating_attenuates_light_entering_the_base() {
+        let clear = Subsurface::new(Color::new(0.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.0), 1.3);
+        let material = Layered::new(Box::new(clear.clone()), 1.5, 0.0).with_absorption(Color::new(0.5, 1.0, 2.0), 0.1);
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+        let hit = sphere.hit(&Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0))).unwrap();
+        let wo = Vec3::new(0.0, 0.0, -1.0);
+        let bare = clear.subsurface(&wo, &hit, &sphere).unwrap();
+        let coated = material.subsurface(&wo, &hit, &sphere).unwrap();
+        let fresnel = microfacet::fresnel_dielectric(1.0, 1.5);
+        assert_approx_eq!(coated.weight.red, bare.weight.red * (1.0 - fresnel) * (-0.05f64).exp(), 1e-9);
+        assert_approx_eq!(coated.weight.blue, bare.weight.blue * (1.0 - fresnel) * (-0.2f64).exp(), 1e-9);
+        assert_approx_eq!(coated.hit.point.length(), 1.0, 1e-6);
+    }
+}
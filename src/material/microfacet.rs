@@ -0,0 +1,218 @@
+use std::f64::consts::PI;
+use crate::{
+    vec3::Vec3,
+    image::Color,
+};
+
+/// Roughness below which a microfacet lobe is treated as a perfect delta lobe.
+pub const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// Anisotropic GGX (Trowbridge-Reitz) distribution with Smith height-correlated masking.
+/// All directions are in the local shading frame, with the normal along +z.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Ggx {
+        Ggx {
+            alpha_x,
+            alpha_y,
+        }
+    }
+    /// Builds the distribution from perceptual roughness, using alpha = roughness².
+    pub fn from_roughness(roughness_u: f64, roughness_v: f64) -> Ggx {
+        Ggx::new(roughness_u * roughness_u, roughness_v * roughness_v)
+    }
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+    pub fn d(&self, m: &Vec3) -> f64 {
+        if m.z <= 0.0 {
+            return 0.0;
+        }
+        let x = m.x / self.alpha_x;
+        let y = m.y / self.alpha_y;
+        let t = x * x + y * y + m.z * m.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * t * t)
+    }
+    pub fn lambda(&self, w: &Vec3) -> f64 {
+        if w.z == 0.0 {
+            return f64::INFINITY;
+        }
+        let a2 = (self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2);
+        ((1.0 + a2 / (w.z * w.z)).sqrt() - 1.0) / 2.0
+    }
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+    /// Density of visible normals `m` as seen from `w`.
+    pub fn d_visible(&self, w: &Vec3, m: &Vec3) -> f64 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(m) * w.dot(m).abs()
+    }
+    /// Samples a visible normal as seen from `w` (Heitz 2018).
+    pub fn sample_visible(&self, w: &Vec3, u1: f64, u2: f64) -> Vec3 {
+        let mut vh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).normalize();
+        if vh.z < 0.0 {
+            vh = -vh;
+        }
+        let len_sq = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len_sq > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0).scale(1.0 / len_sq.sqrt())
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
+
+/// Unpolarized Fresnel reflectance at a dielectric interface. `eta` is the
+/// ratio of the transmitted to the incident index of refraction.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i, eta)
+    };
+    let cos_i = cos_i.min(1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    (rs * rs + rp * rp) / 2.0
+}
+
+fn fresnel_conductor_channel(cos_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    (rs + rp) / 2.0
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction `eta + i k`.
+pub fn fresnel_conductor(cos_i: f64, eta: &Color, k: &Color) -> Color {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    Color::new(
+        fresnel_conductor_channel(cos_i, eta.red, k.red),
+        fresnel_conductor_channel(cos_i, eta.green, k.green),
+        fresnel_conductor_channel(cos_i, eta.blue, k.blue),
+    )
+}
+
+/// Mirrors `w` about `m`, both pointing away from the surface.
+pub fn reflect(w: &Vec3, m: &Vec3) -> Vec3 {
+    -*w + *m * (2.0 * w.dot(m))
+}
+
+/// Refracts `w` (pointing away from the surface) through `m`. Returns the
+/// transmitted direction and the relative index along the path, or `None` on
+/// total internal reflection.
+pub fn refract(w: &Vec3, m: &Vec3, eta: f64) -> Option<(Vec3, f64)> {
+    let mut cos_i = w.dot(m);
+    let mut eta = eta;
+    let mut m = *m;
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+        m = -m;
+    }
+    let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some((-*w * (1.0 / eta) + m * (cos_i / eta - cos_t), eta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_ggx_projected_area_is_normalized() {
+        // Integral of D(m) cos(theta_m) over the hemisphere must equal one.
+        let ggx = Ggx::new(0.3, 0.6);
+        let n = 400;
+        let mut sum = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let cos_theta = (i as f64 + 0.5) / n as f64;
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let m = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                sum += ggx.d(&m) * cos_theta;
+            }
+        }
+        sum *= 2.0 * PI / (n * n) as f64;
+        assert_approx_eq!(sum, 1.0, 1e-2);
+    }
+
+    #[test]
+    fn test_visible_normal_density_is_normalized() {
+        let ggx = Ggx::new(0.5, 0.2);
+        let w = Vec3::new(0.4, -0.3, 0.8).normalize();
+        let n = 400;
+        let mut sum = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let cos_theta = (i as f64 + 0.5) / n as f64;
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let m = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                if w.dot(&m) > 0.0 {
+                    sum += ggx.d_visible(&w, &m);
+                }
+            }
+        }
+        sum *= 2.0 * PI / (n * n) as f64;
+        assert_approx_eq!(sum, 1.0, 1e-2);
+        let m = ggx.sample_visible(&w, 0.3, 0.7);
+        assert!(m.z > 0.0 && w.dot(&m) > 0.0);
+    }
+
+    #[test]
+    fn test_fresnel_dielectric_normal_incidence() {
+        assert_approx_eq!(fresnel_dielectric(1.0, 1.5), 0.04);
+        assert_approx_eq!(fresnel_dielectric(-1.0, 1.5), 0.04);
+        assert_approx_eq!(fresnel_dielectric(-0.1, 1.5), 1.0);
+    }
+
+    #[test]
+    fn test_fresnel_conductor_matches_dielectric_without_absorption() {
+        let eta = Color::new(1.5, 1.5, 1.5);
+        let k = Color::new(0.0, 0.0, 0.0);
+        for cos_i in [0.1, 0.5, 0.9, 1.0] {
+            assert_approx_eq!(fresnel_conductor(cos_i, &eta, &k).red, fresnel_dielectric(cos_i, 1.5));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{Material, BsdfSample, SubsurfaceExit},
+    geometry::{Geometry, Hit},
+    texture::{Texture, constant::Constant},
+    vec3::Vec3,
+};
+
+/// Linear blend of two materials. A `factor` of 0 gives `first` and 1 gives
+/// `second`; textured factors use the luminance of the texture.
+pub struct Mix {
+    pub first: Box<dyn Material>,
+    pub second: Box<dyn Material>,
+    pub factor: Arc<dyn Texture>,
+}
+
+impl Mix {
+    pub fn new(first: Box<dyn Material>, second: Box<dyn Material>, factor: f64) -> Mix {
+        Mix::textured(first, second, Arc::new(Constant::new(Color::new(factor, factor, factor))))
+    }
+    pub fn textured(first: Box<dyn Material>, second: Box<dyn Material>, factor: Arc<dyn Texture>) -> Mix {
+        Mix {
+            first,
+            second,
+            factor,
+        }
+    }
+    fn factor_at(&self, hit: &Hit) -> f64 {
+        self.factor.value(hit).luminance().clamp(0.0, 1.0)
+    }
+}
+
+impl Material for Mix {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        let t = self.factor_at(hit);
+        self.first.eval(wo, wi, hit) * (1.0 - t) + self.second.eval(wo, wi, hit) * t
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let t = self.factor_at(hit);
+        let (chosen, probability) = if rand::random::<f64>() < t {
+            (&self.second, t)
+        } else {
+            (&self.first, 1.0 - t)
+        };
+        let sample = chosen.sample(wo, hit)?;
+        if sample.specular {
+            return Some(BsdfSample {
+                value: sample.value * probability,
+                pdf: sample.pdf * probability,
+                ..sample
+            });
+        }
+        let pdf = self.pdf(wo, &sample.direction, hit);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            value: self.eval(wo, &sample.direction, hit),
+            pdf,
+            ..sample
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        let t = self.factor_at(hit);
+        self.first.pdf(wo, wi, hit) * (1.0 - t) + self.second.pdf(wo, wi, hit) * t
+    }
+    fn is_specular(&self) -> bool {
+        self.first.is_specular() && self.second.is_specular()
+    }
+    fn specular_lobes(&self, wo: &Vec3, hit: &Hit) -> Vec<BsdfSample> {
+        let t = self.factor_at(hit);
+        let first = self.first.specular_lobes(wo, hit).into_iter().map(|lobe| (lobe, 1.0 - t));
+        let second = self.second.specular_lobes(wo, hit).into_iter().map(|lobe| (lobe, t));
+        first.chain(second).map(|(lobe, weight)| BsdfSample {
+            value: lobe.value * weight,
+            ..lobe
+        }).collect()
+    }
+    fn opacity(&self, hit: &Hit) -> f64 {
+        let t = self.factor_at(hit);
+        self.first.opacity(hit) * (1.0 - t) + self.second.opacity(hit) * t
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        let t = self.factor_at(hit);
+        self.first.transmittance(wi, hit) * (1.0 - t) + self.second.transmittance(wi, hit) * t
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        if rand::random::<f64>() < self.factor_at(hit) {
+            self.second.subsurface(wo, hit, geometry)
+        } else {
+            self.first.subsurface(wo, hit, geometry)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::material::{lambertian::Lambertian, conductor::Conductor};
+
+    fn materials(roughness: f64) -> (Box<dyn Material>, Box<dyn Material>) {
+        (Box::new(Lambertian::new(Color::new(0.8, 0.4, 0.2))), Box::new(Conductor::gold(roughness)))
+    }
+
+    /// Directional albedo of `material` for the green channel, by its own sampling.
+    fn albedo(material: &dyn Material, wo: &Vec3, samples: u32) -> f64 {
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            if let Some(sample) = material.sample(wo, &Hit::at_origin()) {
+                sum += sample.value.green * sample.direction.z.abs() / sample.pdf;
+            }
+        }
+        sum / samples as f64
+    }
+
+    #[test]
+    fn test_eval_and_pdf_blend_both_materials() {
+        let t = 0.3;
+        let (first, second) = materials(0.4);
+        let mix = Mix::new(materials(0.4).0, materials(0.4).1, t);
+        let wo = Vec3::new(0.3, 0.1, 0.9).normalize();
+        for wi in [Vec3::new(-0.3, -0.1, 0.9).normalize(), Vec3::new(0.5, -0.2, 0.6).normalize(), Vec3::new(0.0, 0.0, 1.0)] {
+            let expected = first.eval(&wo, &wi, &Hit::at_origin()) * (1.0 - t) + second.eval(&wo, &wi, &Hit::at_origin()) * t;
+            let value = mix.eval(&wo, &wi, &Hit::at_origin());
+            assert_approx_eq!(value.red, expected.red, 1e-12);
+            assert_approx_eq!(value.blue, expected.blue, 1e-12);
+            let expected = first.pdf(&wo, &wi, &Hit::at_origin()) * (1.0 - t) + second.pdf(&wo, &wi, &Hit::at_origin()) * t;
+            assert_approx_eq!(mix.pdf(&wo, &wi, &Hit::at_origin()), expected, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_sampling_matches_blended_albedo() {
+        let t = 0.3;
+        let wo = Vec3::new(0.3, 0.1, 0.9).normalize();
+        // A rough and a mirror-like second material, the latter taking the specular path.
+        for roughness in [0.4, 0.0] {
+            let (first, second) = materials(roughness);
+            let mix = Mix::new(materials(roughness).0, materials(roughness).1, t);
+            for _ in 0..1000 {
+                let Some(sample) = mix.sample(&wo, &Hit::at_origin()) else { continue };
+                if !sample.specular {
+                    assert_approx_eq!(sample.pdf, mix.pdf(&wo, &sample.direction, &Hit::at_origin()), 1e-9);
+                    assert_approx_eq!(sample.value.green, mix.eval(&wo, &sample.direction, &Hit::at_origin()).green, 1e-9);
+                }
+            }
+            let samples = 200_000;
+            let expected = albedo(first.as_ref(), &wo, samples) * (1.0 - t) + albedo(second.as_ref(), &wo, samples) * t;
+            assert_approx_eq!(albedo(&mix, &wo, samples), expected, 0.01);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    f64::consts::PI,
+    sync::Arc,
+};
+use crate::{
+    image::Color,
+    material::{Material, BsdfSample},
+    geometry::Hit,
+    vec3::Vec3,
+    sampling,
+    texture::{Texture, constant::Constant},
+};
+
+/// Rough diffuse reflection from V-cavity microfacets (qualitative Oren-Nayar model).
+/// `roughness` is the standard deviation of the facet slope angle, in radians;
+/// at zero it reduces to `Lambertian`.
+#[derive(Debug, Clone)]
+pub struct OrenNayar {
+    pub albedo: Arc<dyn Texture>,
+    pub roughness: f64,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: Color, roughness: f64) -> OrenNayar {
+        OrenNayar::textured(Arc::new(Constant::new(albedo)), roughness)
+    }
+    pub fn textured(albedo: Arc<dyn Texture>, roughness: f64) -> OrenNayar {
+        OrenNayar {
+            albedo,
+            roughness,
+        }
+    }
+    fn factor(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let sigma2 = self.roughness * self.roughness;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        let cos_phi = if sin_o > 1e-4 && sin_i > 1e-4 {
+            ((wo.x * wi.x + wo.y * wi.y) / (sin_o * sin_i)).max(0.0)
+        } else {
+            0.0
+        };
+        let (sin_alpha, tan_beta) = if wi.z.abs() > wo.z.abs() {
+            (sin_o, sin_i / wi.z.abs())
+        } else {
+            (sin_i, sin_o / wo.z.abs())
+        };
+        a + b * cos_phi * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        let frame = hit.frame();
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        if wo.z * wi.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.albedo.value(hit) * (self.factor(&wo, &wi) / PI)
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame();
+        let mut local = sampling::cosine_hemisphere(rand::random(), rand::random());
+        if wo.dot(&hit.normal) < 0.0 {
+            local.z = -local.z;
+        }
+        let direction = frame.to_world(&local);
+        Some(BsdfSample {
+            direction,
+            value: self.eval(wo, &direction, hit),
+            pdf: sampling::cosine_hemisphere_pdf(local.z),
+            specular: false,
+            eta: 1.0,
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        if wo.dot(&hit.normal) * wi.dot(&hit.normal) <= 0.0 {
+            return 0.0;
+        }
+        sampling::cosine_hemisphere_pdf(wi.dot(&hit.normal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use crate::material::lambertian::Lambertian;
+
+    fn albedo(material: &dyn Material, wo: &Vec3) -> f64 {
+        let samples = 50_000;
+        let mut albedo = 0.0;
+        for _ in 0..samples {
+            if let Some(sample) = material.sample(wo, &Hit::at_origin()) {
+                albedo += sample.value.red * sample.direction.z.abs() / sample.pdf;
+            }
+        }
+        albedo / samples as f64
+    }
+
+    #[test]
+    fn test_zero_roughness_matches_lambertian() {
+        let color = Color::new(0.7, 0.7, 0.7);
+        let smooth = OrenNayar::new(color, 0.0);
+        let lambertian = Lambertian::new(color);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let wi = Vec3::new(-0.3, 0.4, 0.866).normalize();
+        assert_approx_eq!(smooth.eval(&wo, &wi, &Hit::at_origin()).red, lambertian.eval(&wo, &wi, &Hit::at_origin()).red);
+        assert_approx_eq!(albedo(&smooth, &wo), albedo(&lambertian, &wo), 1e-2);
+    }
+
+    #[test]
+    fn test_rough_albedo_does_not_exceed_lambertian() {
+        let color = Color::new(1.0, 1.0, 1.0);
+        let rough = OrenNayar::new(color, 0.5);
+        for wo in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.6, 0.0, 0.8)] {
+            assert!(albedo(&rough, &wo) <= 1.0 + 1e-2);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    f64::consts::PI,
+    sync::Arc,
+};
+use crate::{
+    image::Color,
+    material::{
+        Material,
+        BsdfSample,
+        dielectric::Dielectric,
+        microfacet::{self, Ggx},
+    },
+    geometry::Hit,
+    vec3::Vec3,
+    sampling,
+    texture::{Texture, constant::Constant},
+};
+
+/// Roughness floor that keeps the principled lobes out of the delta regime.
+const MIN_ROUGHNESS: f64 = 0.04;
+
+/// Disney-style uber material combining diffuse, sheen, specular, clearcoat
+/// and transmission lobes, with parameters in `[0, 1]` unless noted.
+///
+/// glTF metallic-roughness maps directly onto `base_color`, `metallic` and
+/// `roughness`; its default dielectric reflectance of 4% is `specular = 0.5`.
+/// `KHR_materials_clearcoat`, `KHR_materials_sheen`, `KHR_materials_transmission`
+/// and `KHR_materials_ior` map onto the fields of the same name.
+#[derive(Debug, Clone)]
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Dielectric reflectance at normal incidence, scaled so that 1.0 is 8%.
+    pub specular: f64,
+    pub specular_tint: f64,
+    pub sheen: f64,
+    pub sheen_tint: f64,
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    pub transmission: f64,
+    pub subsurface: f64,
+    /// Index of refraction of the transmission lobe.
+    pub ior: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Principled {
+        Principled {
+            base_color: Arc::new(Constant::new(Color::new(0.8, 0.8, 0.8))),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            transmission: 0.0,
+            subsurface: 0.0,
+            ior: 1.5,
+        }
+    }
+}
+
+fn schlick_weight(cos: f64) -> f64 {
+    (1.0 - cos).clamp(0.0, 1.0).powi(5)
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Principled {
+        Principled::textured(Arc::new(Constant::new(base_color)))
+    }
+    pub fn textured(base_color: Arc<dyn Texture>) -> Principled {
+        Principled {
+            base_color,
+            ..Principled::default()
+        }
+    }
+    /// Metallic-roughness parameters as exported by glTF 2.0.
+    pub fn metallic_roughness(base_color: Arc<dyn Texture>, metallic: f64, roughness: f64) -> Principled {
+        Principled {
+            base_color,
+            metallic,
+            roughness,
+            ..Principled::default()
+        }
+    }
+    fn tint(base_color: &Color) -> Color {
+        let luminance = base_color.luminance();
+        if luminance > 0.0 {
+            *base_color * (1.0 / luminance)
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+    fn specular_f0(&self, base_color: &Color) -> Color {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let dielectric = white.lerp(&Principled::tint(base_color), self.specular_tint) * (0.08 * self.specular);
+        dielectric.lerp(base_color, self.metallic)
+    }
+    fn specular_distribution(&self) -> Ggx {
+        let roughness = self.roughness.max(MIN_ROUGHNESS);
+        Ggx::from_roughness(roughness, roughness)
+    }
+    fn clearcoat_distribution(&self) -> Ggx {
+        let roughness = self.clearcoat_roughness.max(MIN_ROUGHNESS);
+        Ggx::from_roughness(roughness, roughness)
+    }
+    fn transmission_lobe(&self) -> Dielectric {
+        Dielectric::new(self.ior, self.roughness.max(MIN_ROUGHNESS))
+    }
+    fn transmission_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+    /// Selection probabilities of the diffuse, specular, clearcoat and
+    /// transmission lobes for a local outgoing direction.
+    fn lobe_probabilities(&self, wo: &Vec3, base_color: &Color) -> [f64; 4] {
+        let transmission = self.transmission_weight();
+        let diffuse = (1.0 - self.metallic) * base_color.luminance();
+        let fresnel = Color::new(1.0, 1.0, 1.0).lerp(&self.specular_f0(base_color), 1.0 - schlick_weight(wo.z.abs()));
+        let specular = fresnel.luminance().max(0.05);
+        let clearcoat = 0.25 * self.clearcoat;
+        let opaque = (diffuse + specular + clearcoat).max(1e-9);
+        let scale = (1.0 - transmission) / opaque;
+        [diffuse * scale, specular * scale, clearcoat * scale, transmission]
+    }
+    /// Reflection lobes for local directions in the upper hemisphere.
+    fn eval_opaque(&self, wo: &Vec3, wi: &Vec3, base_color: &Color) -> Color {
+        let h = *wo + *wi;
+        if h.dot(&h) == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let h = h.normalize();
+        let cos_d = wi.dot(&h);
+        let fl = schlick_weight(wi.z);
+        let fv = schlick_weight(wo.z);
+        let fh = schlick_weight(cos_d);
+
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let fss90 = self.roughness * cos_d * cos_d;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (wi.z + wo.z) - 0.5) + 0.5);
+        let diffuse = *base_color * ((fd + (ss - fd) * self.subsurface) / PI);
+        let sheen = Color::new(1.0, 1.0, 1.0).lerp(&Principled::tint(base_color), self.sheen_tint) * (self.sheen * fh);
+
+        let distribution = self.specular_distribution();
+        let fresnel = self.specular_f0(base_color).lerp(&Color::new(1.0, 1.0, 1.0), fh);
+        let specular = fresnel * (distribution.d(&h) * distribution.g(wo, wi) / (4.0 * wo.z * wi.z));
+
+        let coat = self.clearcoat_distribution();
+        let coat_fresnel = 0.04 + 0.96 * fh;
+        let clearcoat = 0.25 * self.clearcoat * coat_fresnel * coat.d(&h) * coat.g(wo, wi) / (4.0 * wo.z * wi.z);
+
+        (diffuse + sheen) * (1.0 - self.metallic) + specular + Color::new(clearcoat, clearcoat, clearcoat)
+    }
+    fn pdf_opaque(&self, wo: &Vec3, wi: &Vec3, probabilities: &[f64; 4]) -> f64 {
+        let h = (*wo + *wi).normalize();
+        let specular = self.specular_distribution().d_visible(wo, &h) / (4.0 * wo.dot(&h));
+        let clearcoat = self.clearcoat_distribution().d_visible(wo, &h) / (4.0 * wo.dot(&h));
+        probabilities[0] * sampling::cosine_hemisphere_pdf(wi.z)
+            + probabilities[1] * specular
+            + probabilities[2] * clearcoat
+    }
+}
+
+impl Material for Principled {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> Color {
+        let transmission = self.transmission_weight();
+        let mut value = if transmission > 0.0 {
+            self.transmission_lobe().eval(wo, wi, hit) * transmission
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        };
+        let base_color = self.base_color.value(hit);
+        let (wo_local, wi_local) = hit.frame().to_local_facing(wo, wi);
+        if wo_local.z > 0.0 && wi_local.z > 0.0 {
+            value += self.eval_opaque(&wo_local, &wi_local, &base_color) * (1.0 - transmission);
+        } else if wi_local.z < 0.0 {
+            value = value * base_color;
+        }
+        value
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let frame = hit.frame().facing(wo);
+        let wo_local = frame.to_local(wo);
+        if wo_local.z <= 0.0 {
+            return None;
+        }
+        let probabilities = self.lobe_probabilities(&wo_local, &self.base_color.value(hit));
+        let u = rand::random::<f64>();
+        let (direction, eta) = if u < probabilities[0] + probabilities[1] + probabilities[2] {
+            let wi_local = if u < probabilities[0] {
+                sampling::cosine_hemisphere(rand::random(), rand::random())
+            } else {
+                let distribution = if u < probabilities[0] + probabilities[1] {
+                    self.specular_distribution()
+                } else {
+                    self.clearcoat_distribution()
+                };
+                let m = distribution.sample_visible(&wo_local, rand::random(), rand::random());
+                microfacet::reflect(&wo_local, &m)
+            };
+            if wi_local.z <= 0.0 {
+                return None;
+            }
+            (frame.to_world(&wi_local), 1.0)
+        } else {
+            let sample = self.transmission_lobe().sample(wo, hit)?;
+            (sample.direction, sample.eta)
+        };
+        let pdf = self.pdf(wo, &direction, hit);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            value: self.eval(wo, &direction, hit),
+            pdf,
+            specular: false,
+            eta,
+        })
+    }
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &Hit) -> f64 {
+        let (wo_local, wi_local) = hit.frame().to_local_facing(wo, wi);
+        if wo_local.z <= 0.0 {
+            return 0.0;
+        }
+        let probabilities = self.lobe_probabilities(&wo_local, &self.base_color.value(hit));
+        let mut pdf = if probabilities[3] > 0.0 {
+            probabilities[3] * self.transmission_lobe().pdf(wo, wi, hit)
+        } else {
+            0.0
+        };
+        if wi_local.z > 0.0 {
+            pdf += self.pdf_opaque(&wo_local, &wi_local, &probabilities);
+        }
+        pdf
+    }
+    fn transmittance(&self, wi: &Vec3, hit: &Hit) -> Color {
+        let transmission = self.transmission_weight();
+        if transmission <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.transmission_lobe().transmittance(wi, hit) * self.base_color.value(hit) * transmission
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_sampling_is_consistent_with_eval_and_pdf() {
+        let material = Principled {
+            metallic: 0.3,
+            roughness: 0.4,
+            sheen: 0.5,
+            clearcoat: 1.0,
+            transmission: 0.5,
+            subsurface: 0.2,
+            ..Principled::new(Color::new(0.7, 0.4, 0.2))
+        };
+        let hit = Hit::at_origin();
+        let wo = Vec3::new(0.2, 0.5, 0.7).normalize();
+        let samples = 100_000;
+        let mut albedo = 0.0;
+        for _ in 0..samples {
+            if let Some(sample) = material.sample(&wo, &hit) {
+                assert_approx_eq!(sample.pdf, material.pdf(&wo, &sample.direction, &hit), 1e-9);
+                albedo += sample.value.red * sample.direction.z.abs() / sample.pdf;
+            }
+        }
+        albedo /= samples as f64;
+        assert!(albedo > 0.0 && albedo < 1.05, "albedo {}", albedo);
+    }
+}
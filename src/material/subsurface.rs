@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    material::{
+        Material,
+        BsdfSample,
+        SubsurfaceExit,
+        microfacet,
+    },
+    geometry::{Geometry, Hit},
+    ray::Ray,
+    vec3::Vec3,
+    frame::Frame,
+    sampling,
+    texture::{Texture, constant::Constant},
+};
+
+/// Offset used to keep walk rays from re-hitting the surface they start on.
+const SURFACE_OFFSET: f64 = 1e-6;
+
+/// Translucent material scattering light inside a closed object with a
+/// volumetric random walk, for skin, wax, marble or milk.
+///
+/// Coefficients are per unit length and per color channel. The smooth
+/// boundary reflects specularly; the rest of the light enters the object and
+/// leaves it elsewhere, with an approximately diffuse distribution. Textured
+/// coefficients are looked up where the light enters.
+#[derive(Debug, Clone)]
+pub struct Subsurface {
+    pub scattering: Arc<dyn Texture>,
+    pub absorption: Arc<dyn Texture>,
+    pub ior: f64,
+    pub max_bounces: u32,
+}
+
+/// Hemispherical average of the dielectric Fresnel reflectance.
+fn diffuse_fresnel(ior: f64) -> f64 {
+    let n = 64;
+    (0..n).map(|i| {
+        let cos = (i as f64 + 0.5) / n as f64;
+        2.0 * cos * microfacet::fresnel_dielectric(cos, ior)
+    }).sum::<f64>() / n as f64
+}
+
+fn channel(color: &Color, i: usize) -> f64 {
+    match i {
+        0 => color.red,
+        1 => color.green,
+        _ => color.blue,
+    }
+}
+
+fn transmittance(extinction: &Color, distance: f64) -> Color {
+    Color::new(
+        (-extinction.red * distance).exp(),
+        (-extinction.green * distance).exp(),
+        (-extinction.blue * distance).exp(),
+    )
+}
+
+/// Scattering or absorption coefficient derived from an albedo texture.
+#[derive(Debug)]
+struct Coefficient {
+    albedo: Arc<dyn Texture>,
+    extinction: Color,
+    absorbing: bool,
+}
+
+impl Texture for Coefficient {
+    fn value(&self, hit: &Hit) -> Color {
+        let albedo = self.albedo.value(hit);
+        if self.absorbing {
+            (Color::new(1.0, 1.0, 1.0) - albedo) * self.extinction
+        } else {
+            albedo * self.extinction
+        }
+    }
+}
+
+impl Subsurface {
+    pub fn new(scattering: Color, absorption: Color, ior: f64) -> Subsurface {
+        Subsurface::textured(Arc::new(Constant::new(scattering)), Arc::new(Constant::new(absorption)), ior)
+    }
+    pub fn textured(scattering: Arc<dyn Texture>, absorption: Arc<dyn Texture>, ior: f64) -> Subsurface {
+        Subsurface {
+            scattering,
+            absorption,
+            ior,
+            max_bounces: 256,
+        }
+    }
+    /// Parameterizes the medium by its single-scattering albedo and the mean
+    /// free path between interactions, which is the distance light travels
+    /// into the object before scattering.
+    pub fn from_albedo(albedo: Color, mean_free_path: Color, ior: f64) -> Subsurface {
+        Subsurface::from_albedo_texture(Arc::new(Constant::new(albedo)), mean_free_path, ior)
+    }
+    /// Like [`Subsurface::from_albedo`], with the albedo varying over the surface.
+    pub fn from_albedo_texture(albedo: Arc<dyn Texture>, mean_free_path: Color, ior: f64) -> Subsurface {
+        let extinction = Color::new(
+            1.0 / mean_free_path.red,
+            1.0 / mean_free_path.green,
+            1.0 / mean_free_path.blue,
+        );
+        Subsurface::textured(
+            Arc::new(Coefficient {
+                albedo: albedo.clone(),
+                extinction,
+                absorbing: false,
+            }),
+            Arc::new(Coefficient {
+                albedo,
+                extinction,
+                absorbing: true,
+            }),
+            ior,
+        )
+    }
+}
+
+impl Material for Subsurface {
+    fn eval(&self, _wo: &Vec3, _wi: &Vec3, _hit: &Hit) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    fn sample(&self, wo: &Vec3, hit: &Hit) -> Option<BsdfSample> {
+        let cos = wo.dot(&hit.normal);
+        let fresnel = microfacet::fresnel_dielectric(cos, self.ior);
+        let direction = hit.normal * (2.0 * cos) - *wo;
+        Some(BsdfSample {
+            direction,
+            value: Color::new(1.0, 1.0, 1.0) * (fresnel / cos.abs()),
+            pdf: 1.0,
+            specular: true,
+            eta: 1.0,
+        })
+    }
+    fn pdf(&self, _wo: &Vec3, _wi: &Vec3, _hit: &Hit) -> f64 {
+        0.0
+    }
+    fn is_specular(&self) -> bool {
+        true
+    }
+    fn subsurface(&self, wo: &Vec3, hit: &Hit, geometry: &dyn Geometry) -> Option<SubsurfaceExit> {
+        let cos = wo.dot(&hit.normal);
+        if cos <= 0.0 {
+            return None;
+        }
+        let boundary = 1.0 - diffuse_fresnel(self.ior);
+        let mut weight = Color::new(1.0, 1.0, 1.0) * ((1.0 - microfacet::fresnel_dielectric(cos, self.ior)) * boundary);
+        let mut local = sampling::cosine_hemisphere(rand::random(), rand::random());
+        local.z = -local.z;
+        let mut direction = Frame::from_normal(&hit.normal).to_world(&local);
+        let mut point = hit.point - hit.normal * SURFACE_OFFSET;
+        let scattering = self.scattering.value(hit);
+        let extinction = scattering + self.absorption.value(hit);
+        for _ in 0..self.max_bounces {
+            let exit = geometry.hit(&Ray::new(point, direction))?;
+            let sampled = channel(&extinction, (rand::random::<f64>() * 3.0) as usize);
+            let distance = -(1.0 - rand::random::<f64>()).ln() / sampled;
+            if distance >= exit.distance {
+                let survival = transmittance(&extinction, exit.distance);
+                let probability = (survival.red + survival.green + survival.blue) / 3.0;
+                weight = weight * survival * (1.0 / probability);
+                return Some(SubsurfaceExit {
+                    hit: exit,
+                    weight,
+                });
+            }
+            let attenuation = transmittance(&extinction, distance);
+            let pdf = (extinction.red * attenuation.red
+                + extinction.green * attenuation.green
+                + extinction.blue * attenuation.blue) / 3.0;
+            weight = weight * scattering * attenuation * (1.0 / pdf);
+            point = point + direction * distance;
+            direction = sampling::uniform_sphere(rand::random(), rand::random());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::sphere::Sphere;
+
+    #[test]
+    fn test_walk_exits_on_the_surface_and_loses_energy() {
+        let material = Subsurface::from_albedo(Color::new(0.9, 0.6, 0.3), Color::new(0.1, 0.1, 0.1), 1.3);
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+        let hit = sphere.hit(&Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0))).unwrap();
+        let wo = Vec3::new(0.0, 0.0, -1.0);
+        let samples = 2000;
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            if let Some(exit) = material.subsurface(&wo, &hit, &sphere) {
+                assert!((exit.hit.point.length() - 1.0).abs() < 1e-6);
+                total += exit.weight;
+            }
+        }
+        let mean = total / samples as f64;
+        assert!(mean.red < 1.0 && mean.red > mean.green && mean.green > mean.blue);
+    }
+    #[test]
+    fn test_walk_without_absorption_keeps_the_transmitted_energy() {
+        let ior = 1.3;
+        let material = Subsurface::new(Color::new(2.0, 2.0, 2.0), Color::new(0.0, 0.0, 0.0), ior);
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+        let hit = sphere.hit(&Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0))).unwrap();
+        let wo = Vec3::new(0.0, 0.0, -1.0);
+        let samples = 2000;
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            if let Some(exit) = material.subsurface(&wo, &hit, &sphere) {
+                total += exit.weight;
+            }
+        }
+        let mean = total / samples as f64;
+        let expected = (1.0 - microfacet::fresnel_dielectric(1.0, ior)) * (1.0 - diffuse_fresnel(ior));
+        assert!((mean.red - expected).abs() < 0.01 * expected);
+        assert!((mean.blue - expected).abs() < 0.01 * expected);
+    }
+}
@@ -1,16 +1,81 @@
+use std::f64::consts::PI;
 use crate::{
     material,
-    material::Material,
+    material::{Material, SubsurfaceExit},
     geometry,
     geometry::{Geometry, Hit},
-    ray::Ray,
-    light::Light,
+    ray::{Ray, RayKind},
+    vec3::Vec3,
+    light::{Light, LightSample, sampler::LightIndex},
+    scene::Scene,
+    sampling,
     image::Color,
 };
 
+/// Step taken past a transparent hit before looking for the next one.
+const TRANSPARENT_OFFSET: f64 = 1e-6;
+const MAX_TRANSPARENT_HITS: u32 = 64;
+/// Step taken off an emitter's surface so shadow rays do not hit the emitter itself.
+const EMITTER_OFFSET: f64 = 1e-6;
+/// Step taken off a surface before testing visibility from it.
+const VISIBILITY_OFFSET: f64 = 1e-6;
+
+/// Which rays see an object. `shadow` controls whether it casts shadows and
+/// `receive_shadows` whether shadows fall on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visibility {
+    pub camera: bool,
+    pub reflection: bool,
+    pub shadow: bool,
+    pub receive_shadows: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Visibility {
+        Visibility {
+            camera: true,
+            reflection: true,
+            shadow: true,
+            receive_shadows: true,
+        }
+    }
+}
+
+impl Visibility {
+    pub fn sees(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Reflection => self.reflection,
+            RayKind::Shadow => self.shadow,
+        }
+    }
+}
+
+/// What shadow rays let through on their way from a surface to a light.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occlusion {
+    /// Glass and other transmissive surfaces tint the light passing straight
+    /// through them, ignoring refraction, and the object does not shadow
+    /// itself. For integrators that do not follow refraction.
+    Transmissive,
+    /// Only partly transparent surfaces let light through, so glass casts a
+    /// full shadow, and the object can shadow itself. For integrators that
+    /// find the light focused through glass by following refraction.
+    Exact,
+}
+
 pub struct Object{
     pub material: Box<dyn Material>,
     pub geometry: Box<dyn Geometry>,
+    /// Name lights refer to when linked to particular objects.
+    pub name: String,
+    pub visibility: Visibility,
+}
+
+impl Default for Object {
+    fn default() -> Object {
+        Object::new(material::default(), geometry::default())
+    }
 }
 
 impl Object {
@@ -18,44 +83,217 @@ impl Object {
         Object {
             material,
             geometry,
+            name: String::new(),
+            visibility: Visibility::default(),
         }
     }
-    pub fn default() -> Object {
-        Object {
-            material: material::default(),
-            geometry: geometry::default(),
-        }
+    pub fn with_name(mut self, name: &str) -> Object {
+        self.name = name.to_string();
+        self
+    }
+    pub fn with_visibility(mut self, visibility: Visibility) -> Object {
+        self.visibility = visibility;
+        self
     }
+    /// Nearest hit along `ray`, passing through the transparent parts of the material.
     pub fn hit(&self, ray: &Ray) -> Option<Hit> {
-        self.geometry.hit(ray)
+        if !self.visibility.sees(ray.kind) {
+            return None;
+        }
+        let direction = ray.direction.normalize();
+        let mut origin = ray.origin;
+        let mut travelled = 0.0;
+        for _ in 0..MAX_TRANSPARENT_HITS {
+            let mut hit = self.geometry.hit(&Ray::new(origin, direction).with_kind(ray.kind))?;
+            let opacity = self.material.opacity(&hit);
+            if opacity >= 1.0 || rand::random::<f64>() < opacity {
+                hit.distance += travelled;
+                return Some(hit);
+            }
+            travelled += hit.distance + TRANSPARENT_OFFSET;
+            origin = hit.point + direction * TRANSPARENT_OFFSET;
+        }
+        None
+    }
+    pub fn shade(&self, ray: &Ray, hit: &Hit, scene: &Scene, depth: u32) -> Color {
+        let wo = -ray.direction.normalize();
+        self.direct(ray, hit, scene, false, Occlusion::Transmissive, depth) + self.material.emitted(&wo, hit)
+    }
+    /// Light from the lights entering beneath the surface at `hit` and leaving
+    /// elsewhere on the object towards `wo`; black unless the material
+    /// scatters volumetrically.
+    pub fn subsurface(&self, wo: &Vec3, hit: &Hit, scene: &Scene, occlusion: Occlusion) -> Color {
+        let Some(exit) = self.material.subsurface(wo, hit, self.geometry.as_ref()) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+        self.shade_exit(&exit, scene.lights(), &scene.light_sampler().select(), scene.objects(), occlusion)
+    }
+    /// Light arriving directly from the lights and emissive objects and
+    /// scattered towards the ray's origin, without the object's own emission,
+    /// with shadows cast as `occlusion` says. With `mis` set, light samples
+    /// are weighted against BSDF sampling, which the caller must then account
+    /// for by following a scattered ray.
+    pub fn direct(&self, ray: &Ray, hit: &Hit, scene: &Scene, mis: bool, occlusion: Occlusion, depth: u32) -> Color {
+        let lights = scene.lights();
+        let objects = scene.objects();
+        let sampler = scene.light_sampler();
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        let wo = -ray.direction.normalize();
+        let selected = sampler.select();
+        for &(index, weight) in &selected {
+            let samples = if mis { sampler.expected_samples(index) } else { 0.0 };
+            match index {
+                LightIndex::Light(i) => {
+                    let light = &lights[i];
+                    if !light.illuminates(self) || !light.reaches(&hit.point) {
+                        continue;
+                    }
+                    let light_sample = light.sample(hit);
+                    if light_sample.radiance.is_black() {
+                        continue;
+                    }
+                    let mut light_color = Color::new(0.0, 0.0, 0.0);
+                    self.material.shade(ray, &light_sample, hit, depth + 1, &mut light_color);
+                    if light_color.is_black() {
+                        continue;
+                    }
+                    if mis && !light.is_delta() {
+                        let bsdf_pdf = self.material.pdf(&wo, &light_sample.direction, hit);
+                        light_color = light_color * sampling::power_heuristic(samples * light_sample.pdf, bsdf_pdf);
+                    }
+                    color += light_color * self.shadow(hit, &light_sample, objects, occlusion) * weight;
+                }
+                LightIndex::Emitter(i) => {
+                    if !std::ptr::eq(self, &objects[i]) {
+                        color += self.shade_emitter(&wo, hit, &objects[i], objects, samples, occlusion) * weight;
+                    }
+                }
+            }
+        }
+        if let Some(exit) = self.material.subsurface(&wo, hit, self.geometry.as_ref()) {
+            color += self.shade_exit(&exit, lights, &selected, objects, occlusion);
+        }
+        color
+    }
+    /// Direct light from an emissive object, sampled uniformly over its surface.
+    /// A nonzero `samples` weighs the sample against BSDF sampling as for lights.
+    fn shade_emitter(&self, wo: &Vec3, hit: &Hit, emitter: &Object, objects: &[Object], samples: f64, occlusion: Occlusion) -> Color {
+        let sample = match emitter.geometry.sample() {
+            Some(sample) => sample,
+            None => return Color::new(0.0, 0.0, 0.0),
+        };
+        let to_light = sample.hit.point - hit.point;
+        let distance = to_light.length();
+        let wi = to_light.scale(1.0 / distance);
+        let cos_light = -wi.dot(&sample.hit.normal);
+        if cos_light <= 0.0 || sample.pdf <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let light_sample = LightSample {
+            direction: wi,
+            distance: distance - EMITTER_OFFSET,
+            radiance: Color::new(1.0, 1.0, 1.0),
+            pdf: 1.0,
+        };
+        let radiance = emitter.material.emitted(&-wi, &sample.hit);
+        let geometry_term = wi.dot(&hit.normal).abs() * cos_light / (distance * distance * sample.pdf);
+        let mut color = self.material.eval(wo, &wi, hit) * radiance * geometry_term;
+        if color.is_black() {
+            return color;
+        }
+        if samples > 0.0 {
+            let light_pdf = sample.pdf * distance * distance / cos_light;
+            color = color * sampling::power_heuristic(samples * light_pdf, self.material.pdf(wo, &wi, hit));
+        }
+        color * self.shadow(hit, &light_sample, objects, occlusion)
     }
-    pub fn shade(&self, ray: &Ray, hit: &Hit, lights: &Vec<Box<dyn Light>>, objects: &Vec<Object>, depth: u32) -> Color {
+    /// Direct light leaving a subsurface exit point, which emits diffusely.
+    fn shade_exit(&self, exit: &SubsurfaceExit, lights: &[Box<dyn Light>], selected: &[(LightIndex, f64)], objects: &[Object], occlusion: Occlusion) -> Color {
         let mut color = Color::new(0.0, 0.0, 0.0);
-        for light in lights {
-            let light_sample = light.sample();
-            let light_ray = Ray::new(light_sample, (hit.point - light_sample).normalize());
-            if self.is_shadow(&hit, &light_ray, objects) {
+        for &(index, weight) in selected {
+            let LightIndex::Light(i) = index else { continue };
+            if !lights[i].illuminates(self) || !lights[i].reaches(&exit.hit.point) {
+                continue;
+            }
+            let light_sample = lights[i].sample(&exit.hit);
+            if light_sample.radiance.is_black() {
                 continue;
-            } else {
-                self.material.shade(&light_ray, &hit, &light, depth + 1, &mut color);
             }
+            let cos = light_sample.direction.dot(&exit.hit.normal);
+            if cos <= 0.0 {
+                continue;
+            }
+            let transmittance = self.shadow(&exit.hit, &light_sample, objects, occlusion);
+            color += exit.weight * light_sample.radiance * transmittance * (weight * cos / (PI * light_sample.pdf));
         }
         color
     }
-    fn is_shadow(&self, hit: &Hit, light_ray: &Ray, objects: &Vec<Object>) -> bool {
-        if hit.normal.dot(&light_ray.direction) > 0.0 {
-            return true;
+    /// Fraction of the light sample's radiance that reaches `hit` through the
+    /// objects in between: white when unoccluded, black when fully shadowed,
+    /// and tinted behind partly transparent surfaces, or glass too if
+    /// `occlusion` is `Transmissive`. Whether the light is on the side of the
+    /// surface it can reach is left to the material.
+    pub fn shadow(&self, hit: &Hit, light_sample: &LightSample, objects: &[Object], occlusion: Occlusion) -> Color {
+        let mut transmittance = Color::new(1.0, 1.0, 1.0);
+        if !self.visibility.receive_shadows {
+            return transmittance;
         }
-        for other in objects {
-            if self as *const _ != other as *const _ {
-                if let Some(shadow_hit) = other.hit(light_ray) {
-                    if shadow_hit.distance < (hit.point - light_ray.origin).length() {
-                        return true;
-                    }
+        let (origin, direction, distance) = match occlusion {
+            Occlusion::Transmissive => (hit.point, light_sample.direction, light_sample.distance),
+            Occlusion::Exact => {
+                let side = if light_sample.direction.dot(&hit.normal) < 0.0 { -1.0 } else { 1.0 };
+                let origin = hit.point + hit.normal * (VISIBILITY_OFFSET * side);
+                // Aim at the far end from the offset origin, so the offset
+                // does not graze the surface there.
+                if light_sample.distance.is_finite() {
+                    let to_target = hit.point + light_sample.direction * light_sample.distance - origin;
+                    (origin, to_target, to_target.length())
+                } else {
+                    (origin, light_sample.direction, light_sample.distance)
                 }
             }
+        };
+        let ray = Ray::new(origin, direction).with_kind(RayKind::Shadow);
+        for other in objects {
+            if occlusion == Occlusion::Transmissive && std::ptr::eq(self, other) {
+                continue;
+            }
+            transmittance = transmittance * other.transmittance(&ray, distance, occlusion);
+            if transmittance.is_black() {
+                break;
+            }
         }
-        false
+        transmittance
+    }
+    /// Light passing through every surface of this object along `ray` before
+    /// `max_distance`, combining the material's opacity and, for `Transmissive`
+    /// occlusion, its transmittance.
+    fn transmittance(&self, ray: &Ray, max_distance: f64, occlusion: Occlusion) -> Color {
+        let mut transmittance = Color::new(1.0, 1.0, 1.0);
+        if !self.visibility.sees(ray.kind) {
+            return transmittance;
+        }
+        let direction = ray.direction.normalize();
+        let mut origin = ray.origin;
+        let mut travelled = 0.0;
+        for _ in 0..MAX_TRANSPARENT_HITS {
+            let hit = match self.geometry.hit(&Ray::new(origin, direction)) {
+                Some(hit) if travelled + hit.distance < max_distance => hit,
+                _ => return transmittance,
+            };
+            let opacity = self.material.opacity(&hit);
+            let mut passed = Color::new(1.0, 1.0, 1.0) * (1.0 - opacity);
+            if occlusion == Occlusion::Transmissive {
+                passed += self.material.transmittance(&direction, &hit) * opacity;
+            }
+            transmittance = transmittance * passed;
+            if transmittance.is_black() {
+                return transmittance;
+            }
+            travelled += hit.distance + TRANSPARENT_OFFSET;
+            origin = hit.point + direction * TRANSPARENT_OFFSET;
+        }
+        Color::new(0.0, 0.0, 0.0)
     }
 }
 
@@ -64,18 +302,25 @@ mod test {
     use super::*;
     use crate::{
         vec3::Vec3,
-        ray::Ray,
         geometry::sphere::Sphere,
+        material::{cutout::Cutout, dielectric::Dielectric},
+        light::directional::DirectionalLight,
     };
 
+    /// White light sample four units along +x.
+    fn light_along_x() -> LightSample {
+        LightSample {
+            direction: Vec3::new(1.0, 0.0, 0.0),
+            distance: 4.0,
+            radiance: Color::new(1.0, 1.0, 1.0),
+            pdf: 1.0,
+        }
+    }
+
     #[test]
-    fn test_is_shadow_true() {
-        let hit = Hit{
-            point: Vec3::new(0.0, 0.0, 0.0),
-            normal: Vec3::new(0.0, 0.0, 0.0),
-            distance: 0.0,
-        };
-        let light_ray = Ray::new(Vec3::new(4.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    fn test_shadow_true() {
+        let hit = Hit::at_origin();
+        let light_sample = light_along_x();
         let object = Object::new(
             material::default(),
             Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
@@ -88,16 +333,12 @@ mod test {
             object,
             other,
         ];
-        assert_eq!(true, objects[0].is_shadow(&hit, &light_ray, &objects));
+        assert!(objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
     }
     #[test]
-    fn test_is_shadow_false() {
-        let hit = Hit{
-            point: Vec3::new(0.0, 0.0, 0.0),
-            normal: Vec3::new(0.0, 0.0, 0.0),
-            distance: 0.0,
-        };
-        let light_ray = Ray::new(Vec3::new(4.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    fn test_shadow_false() {
+        let hit = Hit::at_origin();
+        let light_sample = light_along_x();
         let object = Object::new(
             material::default(),
             Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
@@ -110,7 +351,83 @@ mod test {
             object,
             other,
         ];
-        assert_eq!(false, objects[0].is_shadow(&hit, &light_ray, &objects));
+        assert!(!objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
     }
-
-}
\ No newline at end of file
+    #[test]
+    fn test_shadow_cutout() {
+        let hit = Hit::at_origin();
+        let light_sample = light_along_x();
+        let object = Object::new(
+            material::default(),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        );
+        let other = Object::new(
+            Box::new(Cutout::new(material::default(), 0.0)),
+            Box::new(Sphere::new(Vec3::new(2.0, 0.0, 0.0), 1.0)),
+        );
+        let objects = vec![
+            object,
+            other,
+        ];
+        assert!(!objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
+    }
+    #[test]
+    fn test_shadow_at_infinity() {
+        let hit = Hit::at_origin();
+        let light = DirectionalLight::new(Vec3::new(-1.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 1.0);
+        let light_sample = light.sample(&hit);
+        let object = Object::new(
+            material::default(),
+            Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0)),
+        );
+        let other = Object::new(
+            material::default(),
+            Box::new(Sphere::new(Vec3::new(1000.0, 0.0, 0.0), 1.0)),
+        );
+        let objects = vec![
+            object,
+            other,
+        ];
+        assert!(objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
+    }
+    #[test]
+    fn test_shadow_flags() {
+        let hit = Hit::at_origin();
+        let light_sample = light_along_x();
+        let blocker = || Object::new(
+            material::default(),
+            Box::new(Sphere::new(Vec3::new(2.0, 0.0, 0.0), 1.0)),
+        );
+        let no_cast = Visibility { shadow: false, ..Visibility::default() };
+        let objects = vec![Object::default(), blocker().with_visibility(no_cast)];
+        assert!(!objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
+        let no_receive = Visibility { receive_shadows: false, ..Visibility::default() };
+        let objects = vec![Object::default().with_visibility(no_receive), blocker()];
+        assert!(!objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive).is_black());
+        let hidden = blocker().with_visibility(Visibility { camera: false, ..Visibility::default() });
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(hidden.hit(&ray).is_none());
+        assert!(hidden.hit(&ray.with_kind(RayKind::Reflection)).is_some());
+    }
+    #[test]
+    fn test_glass_casts_tinted_shadow() {
+        let hit = Hit {
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 1.0, 0.0),
+            ..Hit::at_origin()
+        };
+        let light_sample = light_along_x();
+        let glass = Object::new(
+            Box::new(Dielectric::new(1.5, 0.0).with_tint(Color::new(1.0, 0.5, 0.5))),
+            Box::new(Sphere::new(Vec3::new(2.0, 0.0, 0.0), 1.0)),
+        );
+        let objects = vec![Object::default(), glass];
+        let transmittance = objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Transmissive);
+        // Two interfaces at normal incidence each pass 96% of the light.
+        assert!((transmittance.red - 0.96 * 0.96).abs() < 1e-3);
+        assert!((transmittance.green - 0.25 * 0.96 * 0.96).abs() < 1e-3);
+        assert!(objects[0].shadow(&hit, &light_sample, &objects, Occlusion::Exact).is_black());
+        let clear = vec![Object::default()];
+        assert_eq!(clear[0].shadow(&hit, &light_sample, &clear, Occlusion::Exact), Color::new(1.0, 1.0, 1.0));
+    }
+}
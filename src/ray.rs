@@ -1,9 +1,18 @@
 use crate::vec3::Vec3;
 
+/// What a ray is traced for, so objects can choose which rays see them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RayKind {
+    Camera,
+    Reflection,
+    Shadow,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    pub kind: RayKind,
 }
 
 impl Ray {
@@ -11,8 +20,13 @@ impl Ray {
         Ray {
             origin,
             direction,
+            kind: RayKind::Camera,
         }
     }
+    pub fn with_kind(mut self, kind: RayKind) -> Ray {
+        self.kind = kind;
+        self
+    }
     pub fn direction(&self, t: f64) -> Vec3 {
         self.origin + self.direction * t
     }
@@ -0,0 +1,161 @@
+use std::f64::consts::PI;
+use crate::vec3::Vec3;
+
+pub fn cosine_hemisphere(u1: f64, u2: f64) -> Vec3 {
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - u1).max(0.0).sqrt())
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta.abs() / PI
+}
+
+pub fn uniform_sphere(u1: f64, u2: f64) -> Vec3 {
+    let z = 1.0 - 2.0 * u1;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_sphere_pdf() -> f64 {
+    1.0 / (4.0 * PI)
+}
+
+/// Direction within `acos(cos_max)` of +z, uniform in solid angle.
+pub fn uniform_cone(u1: f64, u2: f64, cos_max: f64) -> Vec3 {
+    let z = 1.0 - u1 * (1.0 - cos_max);
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_cone_pdf(cos_max: f64) -> f64 {
+    1.0 / (2.0 * PI * (1.0 - cos_max))
+}
+
+/// Point on the unit disk, uniform in area, using the concentric mapping.
+pub fn concentric_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let a = 2.0 * u1 - 1.0;
+    let b = 2.0 * u2 - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Multiple importance sampling weight of a sample drawn with density `pdf`
+/// when another strategy could have drawn it with density `other` (Veach's
+/// power heuristic with an exponent of 2).
+pub fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other * other);
+    if a + b > 0.0 { a / (a + b) } else { 0.0 }
+}
+
+/// Piecewise-constant density over `[0, 1)` proportional to `values`.
+#[derive(Debug, Clone)]
+pub struct Distribution1d {
+    pub values: Vec<f64>,
+    cdf: Vec<f64>,
+    pub integral: f64,
+}
+
+impl Distribution1d {
+    pub fn new(values: Vec<f64>) -> Distribution1d {
+        let n = values.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + values[i].max(0.0) / n as f64;
+        }
+        let integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if integral > 0.0 { *c / integral } else { i as f64 / n as f64 };
+        }
+        Distribution1d {
+            values,
+            cdf,
+            integral,
+        }
+    }
+    /// Returns a point in `[0, 1)`, its density and the index of its segment.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let n = self.values.len();
+        let index = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(n - 1);
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 { (u - self.cdf[index]) / width } else { 0.0 };
+        ((index as f64 + offset) / n as f64, self.pdf(index), index)
+    }
+    pub fn pdf(&self, index: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.values[index].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Piecewise-constant density over `[0, 1)²`, from a row-major grid of `values`.
+#[derive(Debug, Clone)]
+pub struct Distribution2d {
+    rows: Vec<Distribution1d>,
+    marginal: Distribution1d,
+}
+
+impl Distribution2d {
+    pub fn new(values: &[f64], width: usize, height: usize) -> Distribution2d {
+        let rows: Vec<Distribution1d> = (0..height)
+            .map(|y| Distribution1d::new(values[y * width..(y + 1) * width].to_vec()))
+            .collect();
+        let marginal = Distribution1d::new(rows.iter().map(|row| row.integral).collect());
+        Distribution2d {
+            rows,
+            marginal,
+        }
+    }
+    /// Returns a point `(u, v)` and its density.
+    pub fn sample(&self, u1: f64, u2: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(u2);
+        let (u, pdf_u, _) = self.rows[row].sample(u1);
+        ((u, v), pdf_u * pdf_v)
+    }
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let height = self.rows.len();
+        let row = ((v * height as f64) as usize).min(height - 1);
+        let width = self.rows[row].values.len();
+        let column = ((u * width as f64) as usize).min(width - 1);
+        self.rows[row].pdf(column) * self.marginal.pdf(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_distribution_1d() {
+        let distribution = Distribution1d::new(vec![1.0, 3.0, 0.0, 4.0]);
+        assert_approx_eq!(distribution.integral, 2.0);
+        let (x, pdf, index) = distribution.sample(0.3);
+        assert_eq!(index, 1);
+        assert_approx_eq!(pdf, 1.5);
+        assert!((0.25..0.5).contains(&x));
+        let (_, _, index) = distribution.sample(0.6);
+        assert_eq!(index, 3);
+    }
+
+    #[test]
+    fn test_distribution_2d_pdf_matches_sample() {
+        let values = [1.0, 2.0, 3.0, 0.5, 0.0, 6.0];
+        let distribution = Distribution2d::new(&values, 3, 2);
+        for (u1, u2) in [(0.1, 0.2), (0.7, 0.4), (0.5, 0.9)] {
+            let ((u, v), pdf) = distribution.sample(u1, u2);
+            assert_approx_eq!(pdf, distribution.pdf(u, v));
+        }
+    }
+}
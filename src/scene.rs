@@ -1,14 +1,26 @@
+use std::{f64::consts::PI, sync::OnceLock};
 use crate::{
-    object::{Object, self},
+    object::Object,
     ray::Ray,
     image::Color,
-    light::Light,
+    light::{Light, EmissionSample, sampler::{LightSampler, LightIndex}},
     geometry::Hit,
+    frame::Frame,
+    vec3::Vec3,
+    sampling,
 };
 
 pub struct Scene {
     objects: Vec<Object>,
     lights: Vec<Box<dyn Light>>,
+    light_samples: u32,
+    light_sampler: OnceLock<LightSampler>,
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
 }
 
 impl Scene {
@@ -16,30 +28,93 @@ impl Scene {
         Scene {
             objects: Vec::new(),
             lights: Vec::new(),
+            light_samples: 0,
+            light_sampler: OnceLock::new(),
         }
     }
     pub fn add_object(&mut self, object: Object) {
         self.objects.push(object);
+        self.light_sampler = OnceLock::new();
     }
     pub fn add_light(&mut self, light: Box<dyn Light>) {
         self.lights.push(light);
+        self.light_sampler = OnceLock::new();
+    }
+    /// Number of lights, chosen by power, shaded at each hit. With the
+    /// default of 0 every light is shaded.
+    pub fn set_light_samples(&mut self, samples: u32) {
+        self.light_samples = samples;
+        self.light_sampler = OnceLock::new();
+    }
+    pub fn light_sampler(&self) -> &LightSampler {
+        self.light_sampler.get_or_init(|| LightSampler::new(&self.lights, &self.objects, self.light_samples))
+    }
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+    pub fn background(&self, direction: &Vec3) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        for light in &self.lights {
+            color += light.background(direction);
+        }
+        color
     }
-    pub fn color_at(&self, ray: Ray, depth: u32) -> Color {
-        let mut minDist = f64::MAX;
+    /// Nearest object along `ray` and where it is hit.
+    pub fn intersect(&self, ray: &Ray) -> Option<(Hit, &Object)> {
+        let mut min_dist = f64::MAX;
         let mut hit_object: Option<(Hit, &Object)> = None;
         for object in &self.objects {
-            if let Some(hit) = object.hit(&ray) {
-                if hit.distance < minDist {
-                    minDist = hit.distance;
+            if let Some(hit) = object.hit(ray) {
+                if hit.distance < min_dist {
+                    min_dist = hit.distance;
                     hit_object = Some((hit, object));
                 }
             }
         }
-        match hit_object {
-            Some((hit, object)) => 
-                object.shade(&ray, &hit, &self.lights, &self.objects, depth),
-            None => Color::new(0.0, 0.0, 0.0),
+        hit_object
+    }
+    /// Ray leaving light `index`, for tracing light from it, with the surface
+    /// hit for emissive objects, which emit with a cosine distribution.
+    pub fn sample_emission(&self, index: LightIndex) -> Option<(EmissionSample, Option<Hit>)> {
+        match index {
+            LightIndex::Light(i) => Some((self.lights[i].sample_emission()?, None)),
+            LightIndex::Emitter(i) => {
+                let object = &self.objects[i];
+                let sample = object.geometry.sample()?;
+                let local = sampling::cosine_hemisphere(rand::random(), rand::random());
+                let direction = Frame::from_normal(&sample.hit.normal).to_world(&local);
+                let emission = EmissionSample {
+                    point: sample.hit.point,
+                    normal: sample.hit.normal,
+                    direction,
+                    radiance: object.material.emitted(&direction, &sample.hit),
+                    pdf_position: sample.pdf,
+                    pdf_direction: sampling::cosine_hemisphere_pdf(local.z),
+                };
+                Some((emission, Some(sample.hit)))
+            }
+        }
+    }
+    /// Densities with which `sample_emission` picks `point` and `direction`.
+    pub fn emission_pdf(&self, index: LightIndex, point: &Vec3, hit: Option<&Hit>, direction: &Vec3) -> (f64, f64) {
+        match (index, hit) {
+            (LightIndex::Light(i), _) => self.lights[i].pdf_emission(point, direction),
+            (LightIndex::Emitter(i), Some(hit)) => {
+                let cos = direction.dot(&hit.normal);
+                (self.objects[i].geometry.pdf(hit), if cos > 0.0 { cos / PI } else { 0.0 })
+            }
+            (LightIndex::Emitter(_), None) => (0.0, 0.0),
+        }
+    }
+    /// Light leaving `point` on light `index` along `direction`.
+    pub fn emission(&self, index: LightIndex, point: &Vec3, hit: Option<&Hit>, direction: &Vec3) -> Color {
+        match (index, hit) {
+            (LightIndex::Light(i), _) => self.lights[i].emission(point, direction),
+            (LightIndex::Emitter(i), Some(hit)) => self.objects[i].material.emitted(direction, hit),
+            (LightIndex::Emitter(_), None) => Color::new(0.0, 0.0, 0.0),
         }
     }
 }
-
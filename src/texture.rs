@@ -0,0 +1,16 @@
+pub mod constant;
+pub mod checker;
+pub mod image_texture;
+pub mod noise;
+pub mod solid;
+
+use std::fmt::Debug;
+use crate::{
+    image::Color,
+    geometry::Hit,
+};
+
+/// Spatially varying color, evaluated at a hit's surface coordinates or position.
+pub trait Texture: Debug + Send + Sync {
+    fn value(&self, hit: &Hit) -> Color;
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use crate::{
+    image::Color,
+    texture::Texture,
+    geometry::Hit,
+};
+
+/// Alternates two textures in unit cells of the hit's uv coordinates.
+#[derive(Debug, Clone)]
+pub struct Checker2d {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale_u: f64,
+    pub scale_v: f64,
+}
+
+impl Checker2d {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale_u: f64, scale_v: f64) -> Checker2d {
+        Checker2d {
+            even,
+            odd,
+            scale_u,
+            scale_v,
+        }
+    }
+}
+
+impl Texture for Checker2d {
+    fn value(&self, hit: &Hit) -> Color {
+        let (u, v) = hit.uv;
+        let cell = (u * self.scale_u).floor() + (v * self.scale_v).floor();
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.value(hit)
+        } else {
+            self.odd.value(hit)
+        }
+    }
+}
+
+/// Alternates two textures in cubes of side `1 / scale` in world space.
+#[derive(Debug, Clone)]
+pub struct Checker3d {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl Checker3d {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f64) -> Checker3d {
+        Checker3d {
+            even,
+            odd,
+            scale,
+        }
+    }
+}
+
+impl Texture for Checker3d {
+    fn value(&self, hit: &Hit) -> Color {
+        let p = hit.point.scale(self.scale);
+        let cell = p.x.floor() + p.y.floor() + p.z.floor();
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.value(hit)
+        } else {
+            self.odd.value(hit)
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    image::Color,
+    texture::Texture,
+    geometry::Hit,
+};
+
+#[derive(Debug, Clone)]
+pub struct Constant {
+    pub color: Color,
+}
+
+impl Constant {
+    pub fn new(color: Color) -> Constant {
+        Constant {
+            color,
+        }
+    }
+}
+
+impl Texture for Constant {
+    fn value(&self, _hit: &Hit) -> Color {
+        self.color
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::Path;
+use crate::{
+    image::Color,
+    texture::Texture,
+    geometry::Hit,
+};
+
+/// How texture coordinates outside `[0, 1]` are mapped back into the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// Bitmap texture addressed by the hit's uv coordinates, with v pointing up.
+/// Texels are stored as linear radiometric values.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    pub width: u32,
+    pub height: u32,
+    pub wrap: Wrap,
+    pub filter: Filter,
+    texels: Vec<Color>,
+}
+
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl ImageTexture {
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> ImageTexture {
+        assert_eq!(texels.len(), (width * height) as usize);
+        ImageTexture {
+            width,
+            height,
+            wrap: Wrap::Repeat,
+            filter: Filter::Bilinear,
+            texels,
+        }
+    }
+    /// Loads an image file. Color images are normally sRGB encoded, while data
+    /// such as normal or roughness maps are already linear.
+    pub fn open<P: AsRef<Path>>(path: P, srgb: bool) -> Result<ImageTexture, ::image::ImageError> {
+        let image = ::image::open(path)?.into_rgb32f();
+        let decode = |c: f32| if srgb { srgb_to_linear(c as f64) } else { c as f64 };
+        let texels = image.pixels()
+            .map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2])))
+            .collect();
+        Ok(ImageTexture::new(image.width(), image.height(), texels))
+    }
+    /// Loads the alpha channel of an image file as a grayscale texture, for opacity masks.
+    pub fn open_alpha<P: AsRef<Path>>(path: P) -> Result<ImageTexture, ::image::ImageError> {
+        let image = ::image::open(path)?.into_rgba32f();
+        let texels = image.pixels()
+            .map(|p| Color::new(p[3] as f64, p[3] as f64, p[3] as f64))
+            .collect();
+        Ok(ImageTexture::new(image.width(), image.height(), texels))
+    }
+    pub fn with_wrap(mut self, wrap: Wrap) -> ImageTexture {
+        self.wrap = wrap;
+        self
+    }
+    pub fn with_filter(mut self, filter: Filter) -> ImageTexture {
+        self.filter = filter;
+        self
+    }
+    fn wrap_index(&self, i: i64, size: u32) -> usize {
+        let size = size as i64;
+        let index = match self.wrap {
+            Wrap::Repeat => i.rem_euclid(size),
+            Wrap::Clamp => i.clamp(0, size - 1),
+            Wrap::Mirror => {
+                let period = i.rem_euclid(2 * size);
+                if period < size { period } else { 2 * size - 1 - period }
+            }
+        };
+        index as usize
+    }
+    pub fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap_index(x, self.width);
+        let y = self.wrap_index(y, self.height);
+        self.texels[x + y * self.width as usize]
+    }
+    pub fn sample(&self, u: f64, v: f64) -> Color {
+        let x = u * self.width as f64;
+        let y = (1.0 - v) * self.height as f64;
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), tx);
+                let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), tx);
+                top.lerp(&bottom, ty)
+            }
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, hit: &Hit) -> Color {
+        self.sample(hit.uv.0, hit.uv.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn texture() -> ImageTexture {
+        ImageTexture::new(2, 1, vec![Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)])
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        let repeat = texture().with_filter(Filter::Nearest);
+        assert_approx_eq!(repeat.sample(1.25, 0.5).red, 0.0);
+        let clamp = texture().with_filter(Filter::Nearest).with_wrap(Wrap::Clamp);
+        assert_approx_eq!(clamp.sample(1.25, 0.5).red, 1.0);
+        let mirror = texture().with_filter(Filter::Nearest).with_wrap(Wrap::Mirror);
+        assert_approx_eq!(mirror.sample(1.25, 0.5).red, 1.0);
+        assert_approx_eq!(mirror.sample(1.75, 0.5).red, 0.0);
+    }
+
+    #[test]
+    fn test_bilinear_interpolates_between_texel_centers() {
+        let texture = texture().with_wrap(Wrap::Clamp);
+        assert_approx_eq!(texture.sample(0.25, 0.5).red, 0.0);
+        assert_approx_eq!(texture.sample(0.5, 0.5).red, 0.5);
+        assert_approx_eq!(texture.sample(0.75, 0.5).red, 1.0);
+    }
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_approx_eq!(srgb_to_linear(0.0), 0.0);
+        assert_approx_eq!(srgb_to_linear(1.0), 1.0);
+        assert_approx_eq!(srgb_to_linear(0.5), 0.214, 1e-3);
+    }
+}
@@ -0,0 +1,150 @@
+use rand::{
+    rngs::StdRng,
+    seq::SliceRandom,
+    SeedableRng,
+};
+use crate::vec3::Vec3;
+
+/// Seeded lattice used by gradient and cellular noise.
+#[derive(Debug, Clone)]
+pub struct Noise {
+    permutation: [u8; 512],
+}
+
+const GRADIENTS: [(f64, f64, f64); 12] = [
+    (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0), (-1.0, 0.0, 1.0), (1.0, 0.0, -1.0), (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0), (0.0, -1.0, 1.0), (0.0, 1.0, -1.0), (0.0, -1.0, -1.0),
+];
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(seed));
+        let mut permutation = [0; 512];
+        for (i, p) in permutation.iter_mut().enumerate() {
+            *p = values[i % 256];
+        }
+        Noise {
+            permutation,
+        }
+    }
+    fn hash(&self, x: i64, y: i64, z: i64) -> usize {
+        let p = &self.permutation;
+        let a = p[(x & 255) as usize] as usize;
+        let b = p[a + (y & 255) as usize] as usize;
+        p[b + (z & 255) as usize] as usize
+    }
+    fn gradient(&self, x: i64, y: i64, z: i64, dx: f64, dy: f64, dz: f64) -> f64 {
+        let (gx, gy, gz) = GRADIENTS[self.hash(x, y, z) % 12];
+        gx * dx + gy * dy + gz * dz
+    }
+    /// Gradient (Perlin) noise in roughly `[-1, 1]`, zero at lattice points.
+    pub fn perlin(&self, p: &Vec3) -> f64 {
+        let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (dx, dy, dz) = (p.x - x0, p.y - y0, p.z - z0);
+        let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+        let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+        let corner = |i: i64, j: i64, k: i64| {
+            self.gradient(x0 + i, y0 + j, z0 + k, dx - i as f64, dy - j as f64, dz - k as f64)
+        };
+        lerp(
+            lerp(
+                lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+                lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+                v,
+            ),
+            lerp(
+                lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+                lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+                v,
+            ),
+            w,
+        )
+    }
+    /// Fractal Brownian motion: octaves of gradient noise with doubling
+    /// frequency and amplitudes scaled by `gain`.
+    pub fn fbm(&self, p: &Vec3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.perlin(&p.scale(frequency));
+            amplitude *= gain;
+            frequency *= lacunarity;
+        }
+        sum
+    }
+    /// Like `fbm`, but summing absolute values for a billowy, creased look.
+    pub fn turbulence(&self, p: &Vec3, octaves: u32) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.perlin(&p.scale(frequency)).abs();
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum
+    }
+    fn feature_point(&self, x: i64, y: i64, z: i64) -> Vec3 {
+        let h = self.hash(x, y, z);
+        let jitter = |offset: usize| self.permutation[(h + offset) & 511] as f64 / 256.0;
+        Vec3::new(x as f64 + jitter(0), y as f64 + jitter(101), z as f64 + jitter(211))
+    }
+    /// Cellular (Worley) noise: distances to the nearest and second nearest
+    /// of one jittered feature point per unit cell.
+    pub fn worley(&self, p: &Vec3) -> (f64, f64) {
+        let (cx, cy, cz) = (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+        let mut f1 = f64::MAX;
+        let mut f2 = f64::MAX;
+        for i in -1..=1 {
+            for j in -1..=1 {
+                for k in -1..=1 {
+                    let d = (self.feature_point(cx + i, cy + j, cz + k) - *p).length();
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_perlin_is_zero_on_lattice_and_bounded() {
+        let noise = Noise::new(7);
+        assert_approx_eq!(noise.perlin(&Vec3::new(3.0, -2.0, 5.0)), 0.0);
+        for i in 0..1000 {
+            let t = i as f64 * 0.173;
+            let value = noise.perlin(&Vec3::new(t, t * 0.7, -t * 1.3));
+            assert!(value.abs() <= 1.5);
+        }
+    }
+
+    #[test]
+    fn test_seed_determines_pattern() {
+        let p = Vec3::new(0.3, 1.7, -2.2);
+        assert_eq!(Noise::new(1).fbm(&p, 5, 2.0, 0.5), Noise::new(1).fbm(&p, 5, 2.0, 0.5));
+        assert_ne!(Noise::new(1).perlin(&p), Noise::new(2).perlin(&p));
+        let (f1, f2) = Noise::new(3).worley(&p);
+        assert!(f1 <= f2);
+    }
+}
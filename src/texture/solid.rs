@@ -0,0 +1,230 @@
+use std::f64::consts::PI;
+use crate::{
+    image::Color,
+    texture::{Texture, noise::Noise},
+    geometry::Hit,
+};
+
+/// Blends two colors by fractal noise, evaluated at the hit's object-space position.
+#[derive(Debug, Clone)]
+pub struct Fbm {
+    pub noise: Noise,
+    pub scale: f64,
+    pub octaves: u32,
+    pub low: Color,
+    pub high: Color,
+}
+
+impl Fbm {
+    pub fn new(seed: u64, scale: f64, octaves: u32, low: Color, high: Color) -> Fbm {
+        Fbm {
+            noise: Noise::new(seed),
+            scale,
+            octaves,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for Fbm {
+    fn value(&self, hit: &Hit) -> Color {
+        let n = self.noise.fbm(&hit.object_point.scale(self.scale), self.octaves, 2.0, 0.5);
+        self.low.lerp(&self.high, (0.5 + 0.5 * n).clamp(0.0, 1.0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Turbulence {
+    pub noise: Noise,
+    pub scale: f64,
+    pub octaves: u32,
+    pub low: Color,
+    pub high: Color,
+}
+
+impl Turbulence {
+    pub fn new(seed: u64, scale: f64, octaves: u32, low: Color, high: Color) -> Turbulence {
+        Turbulence {
+            noise: Noise::new(seed),
+            scale,
+            octaves,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, hit: &Hit) -> Color {
+        let n = self.noise.turbulence(&hit.object_point.scale(self.scale), self.octaves);
+        self.low.lerp(&self.high, n.clamp(0.0, 1.0))
+    }
+}
+
+/// Veined stone: sine bands along x, distorted by turbulence.
+#[derive(Debug, Clone)]
+pub struct Marble {
+    pub noise: Noise,
+    pub scale: f64,
+    pub distortion: f64,
+    pub base: Color,
+    pub vein: Color,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f64, distortion: f64, base: Color, vein: Color) -> Marble {
+        Marble {
+            noise: Noise::new(seed),
+            scale,
+            distortion,
+            base,
+            vein,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, hit: &Hit) -> Color {
+        let p = hit.object_point.scale(self.scale);
+        let bands = (p.x + self.distortion * self.noise.turbulence(&p, 6)).sin();
+        self.vein.lerp(&self.base, (0.5 + 0.5 * bands).powf(0.5))
+    }
+}
+
+/// Growth rings around the object-space y axis, perturbed by noise.
+#[derive(Debug, Clone)]
+pub struct Wood {
+    pub noise: Noise,
+    pub rings: f64,
+    pub distortion: f64,
+    pub light: Color,
+    pub dark: Color,
+}
+
+impl Wood {
+    pub fn new(seed: u64, rings: f64, distortion: f64, light: Color, dark: Color) -> Wood {
+        Wood {
+            noise: Noise::new(seed),
+            rings,
+            distortion,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, hit: &Hit) -> Color {
+        let p = hit.object_point;
+        let radius = (p.x * p.x + p.z * p.z).sqrt() + self.distortion * self.noise.perlin(&p.scale(4.0));
+        let ring = (0.5 + 0.5 * (2.0 * PI * radius * self.rings).sin()).powi(3);
+        self.light.lerp(&self.dark, ring)
+    }
+}
+
+/// Cell pattern from Worley noise, dark along the borders between cells.
+#[derive(Debug, Clone)]
+pub struct Cellular {
+    pub noise: Noise,
+    pub scale: f64,
+    pub cell: Color,
+    pub border: Color,
+}
+
+impl Cellular {
+    pub fn new(seed: u64, scale: f64, cell: Color, border: Color) -> Cellular {
+        Cellular {
+            noise: Noise::new(seed),
+            scale,
+            cell,
+            border,
+        }
+    }
+}
+
+impl Texture for Cellular {
+    fn value(&self, hit: &Hit) -> Color {
+        let (f1, f2) = self.noise.worley(&hit.object_point.scale(self.scale));
+        self.border.lerp(&self.cell, ((f2 - f1) * 4.0).clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    fn textures(seed: u64) -> Vec<Box<dyn Texture>> {
+        let (black, white) = (Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        vec![
+            Box::new(Fbm::new(seed, 3.0, 5, black, white)),
+            Box::new(Turbulence::new(seed, 3.0, 5, black, white)),
+            Box::new(Marble::new(seed, 3.0, 4.0, black, white)),
+            Box::new(Wood::new(seed, 4.0, 0.3, black, white)),
+            Box::new(Cellular::new(seed, 3.0, black, white)),
+        ]
+    }
+
+    /// Hit at world position `point` and object-space position `object_point`.
+    fn hit_at(point: Vec3, object_point: Vec3) -> Hit {
+        Hit {
+            distance: 1.0,
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            point,
+            object_point,
+            uv: (point.x, point.z),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            barycentrics: (0.0, 0.0),
+        }
+    }
+
+    fn random_point() -> Vec3 {
+        Vec3::new(rand::random::<f64>() * 4.0 - 2.0, rand::random::<f64>() * 4.0 - 2.0, rand::random::<f64>() * 4.0 - 2.0)
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_pattern() {
+        let (first, second, other) = (textures(7), textures(7), textures(8));
+        let points: Vec<Vec3> = (0..200).map(|_| random_point()).collect();
+        for i in 0..first.len() {
+            let mut differs = false;
+            for &p in &points {
+                let hit = hit_at(p, p);
+                assert_eq!(first[i].value(&hit), second[i].value(&hit));
+                differs |= first[i].value(&hit) != other[i].value(&hit);
+            }
+            assert!(differs, "texture {} ignores its seed", i);
+        }
+    }
+
+    #[test]
+    fn test_values_stay_between_colors() {
+        for texture in textures(3) {
+            for _ in 0..2000 {
+                let p = random_point();
+                let value = texture.value(&hit_at(p, p));
+                for channel in [value.red, value.green, value.blue] {
+                    assert!((0.0..=1.0).contains(&channel), "{:?} gives {}", texture, channel);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pattern_follows_object_space() {
+        for texture in textures(5) {
+            let mut varies = false;
+            let reference = texture.value(&hit_at(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.3, 0.2, 0.1)));
+            for _ in 0..200 {
+                let object_point = random_point();
+                // Moving the object in the world leaves its pattern in place.
+                let value = texture.value(&hit_at(object_point, object_point));
+                let moved = texture.value(&hit_at(object_point + Vec3::new(5.0, -3.0, 2.0), object_point));
+                assert_eq!(value, moved);
+                varies |= value != reference;
+            }
+            assert!(varies, "{:?} is constant", texture);
+        }
+    }
+}
@@ -3,45 +3,51 @@ use std::{
 };
 use crate::camera::Camera;
 use crate::scene::Scene;
+use crate::integrator::Integrator;
 use crate::image::{Image, Color};
 
 pub struct Tracer {
     scene: Scene,
     camera: Camera,
+    integrator: Box<dyn Integrator>,
     width: u32,
     height: u32,
     samples: u32,
-    max_depth: u32,
 }
 
 impl Tracer {
-    pub fn new(scene: Scene, camera: Camera, width: u32, height: u32, samples: u32, max_depth: u32) -> Tracer {
+    pub fn new(scene: Scene, camera: Camera, integrator: Box<dyn Integrator>, width: u32, height: u32, samples: u32) -> Tracer {
         Tracer {
             scene,
             camera,
+            integrator,
             width,
             height,
             samples,
-            max_depth,
         }
     }
-    pub fn render(&self) -> Image {
-        let mut image = Image::new(self.width, self.height);
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut color = Color::new(0.0, 0.0, 0.0);
-                for _ in 0..self.samples {
+    /// Renders one sample per pixel in each of `samples` passes.
+    pub fn render(&mut self) -> Image {
+        let mut sums = vec![Color::new(0.0, 0.0, 0.0); (self.width * self.height) as usize];
+        for pass in 0..self.samples {
+            self.integrator.begin_pass(&self.scene, pass);
+            for y in 0..self.height {
+                for x in 0..self.width {
                     let u = (x as f64 + rand::random::<f64>()) / self.width as f64;
                     let v = (y as f64 + rand::random::<f64>()) / self.height as f64;
                     let ray = self.camera.get_ray(u, v);
-                    color += self.scene.color_at(ray, self.max_depth);
+                    sums[(y * self.width + x) as usize] += self.integrator.radiance(&self.scene, ray);
                 }
-                color = color / self.samples as f64;
-                image.set_pixel(x, y, color);
             }
-            print!("\r{}%", ((y as f64 / self.height as f64) * 100.0 as f64).round());
+            print!("\r{}%", ((pass as f64 / self.samples as f64) * 100.0).round());
             stdout().flush().unwrap();
         }
+        let mut image = Image::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                image.set_pixel(x, y, sums[(y * self.width + x) as usize] / self.samples as f64);
+            }
+        }
         println!("\r completed");
         image
     }
@@ -66,7 +66,7 @@ impl Vec3 {
         let mut rng = rand::thread_rng();
         let mut x = rng.gen::<f64>();
         let mut y = rng.gen::<f64>();
-        let mut z = 0.0;
+        let z = 0.0;
         while x * x + y * y > 1.0 {
             x = rng.gen::<f64>();
             y = rng.gen::<f64>();